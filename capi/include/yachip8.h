@@ -0,0 +1,56 @@
+/* yachip_8 C API, generated by cbindgen from capi/src/lib.rs.
+ * Functions returning bool return false on failure, yachip8_last_error() then describes it.
+ * yachip8_create() returns NULL for an unknown platform name, NULL selects chip8.
+ * Panics inside the emulator are caught at every entry point and reported as failures.
+ * yachip8_run_cycles() only runs the cpu, timers tick once per yachip8_run_frame(). */
+
+#ifndef YACHIP8_H
+#define YACHIP8_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define YACHIP8_SCREEN_WIDTH 64
+
+#define YACHIP8_SCREEN_HEIGHT 32
+
+#define YACHIP8_KEY_COUNT 16
+
+typedef struct Yachip8 Yachip8;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+struct Yachip8 *yachip8_create(const char *platform, uint64_t seed);
+
+void yachip8_destroy(struct Yachip8 *chip8);
+
+const char *yachip8_last_error(const struct Yachip8 *chip8);
+
+bool yachip8_load_rom(struct Yachip8 *chip8, const uint8_t *data, size_t size);
+
+bool yachip8_run_cycles(struct Yachip8 *chip8, uint32_t cycles);
+
+bool yachip8_run_frame(struct Yachip8 *chip8);
+
+void yachip8_key_down(struct Yachip8 *chip8, uint8_t key);
+
+void yachip8_key_up(struct Yachip8 *chip8, uint8_t key);
+
+const uint8_t *yachip8_framebuffer(const struct Yachip8 *chip8);
+
+size_t yachip8_state_size(const struct Yachip8 *chip8);
+
+bool yachip8_serialize(struct Yachip8 *chip8, uint8_t *buffer, size_t size);
+
+bool yachip8_deserialize(struct Yachip8 *chip8, const uint8_t *data, size_t size);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* YACHIP8_H */
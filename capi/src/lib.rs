@@ -0,0 +1,171 @@
+#![allow(clippy::missing_safety_doc)]
+
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+use std::slice;
+
+use yachip_8::unwind::catch;
+use yachip_8::machine::Machine;
+use yachip_8::platform::Platform;
+use yachip_8::quirks::Quirks;
+use yachip_8::timing::Timing;
+use yachip_8::{CYCLES_PER_FRAME, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+pub const YACHIP8_SCREEN_WIDTH: usize = 64;
+pub const YACHIP8_SCREEN_HEIGHT: usize = 32;
+pub const YACHIP8_KEY_COUNT: u8 = 16;
+
+const _: () = assert!(YACHIP8_SCREEN_WIDTH == SCREEN_WIDTH && YACHIP8_SCREEN_HEIGHT == SCREEN_HEIGHT);
+
+pub struct Yachip8{
+    machine: Machine,
+    seed: u64,
+    platform: Platform,
+    keys: [u8; 16],
+    error: CString,
+}
+
+impl Yachip8{
+    fn fail(&mut self, error: String) -> bool{
+        self.error = CString::new(error.replace('\0', "")).unwrap();
+        false
+    }
+
+    fn check<T, E: ToString>(&mut self, result: Result<T, E>) -> bool{
+        match result{
+            Ok(_) => true,
+            Err(error) => self.fail(error.to_string()),
+        }
+    }
+
+    fn guard<F: FnOnce(&mut Yachip8) -> Result<(), String>>(&mut self, f: F) -> bool{
+        let result = catch(|| f(self));
+        self.check(result)
+    }
+}
+
+fn guard<T, F: FnOnce() -> T>(fallback: T, f: F) -> T{
+    catch(|| Ok(f())).unwrap_or(fallback)
+}
+
+fn new_machine(rom: &[u8], seed: u64, platform: Platform) -> Result<Machine, String>{
+    Machine::new(rom, seed, platform, Quirks::default(), Timing::Fixed(CYCLES_PER_FRAME))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachip8_create(platform: *const c_char, seed: u64) -> *mut Yachip8{
+    guard(ptr::null_mut(), || {
+        let platform = if platform.is_null(){
+            Platform::Chip8
+        }else{
+            match CStr::from_ptr(platform).to_str().ok().and_then(Platform::named){
+                Some(platform) => platform,
+                None => return ptr::null_mut(),
+            }
+        };
+        let Ok(machine) = new_machine(&[], seed, platform) else { return ptr::null_mut() };
+        Box::into_raw(Box::new(Yachip8{
+            machine,
+            seed,
+            platform,
+            keys: [0; 16],
+            error: CString::default(),
+        }))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachip8_destroy(chip8: *mut Yachip8){
+    if !chip8.is_null(){
+        guard((), || drop(Box::from_raw(chip8)));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn yachip8_last_error(chip8: Option<&Yachip8>) -> *const c_char{
+    match chip8{
+        Some(chip8) => chip8.error.as_ptr(),
+        None => c"no yachip8 instance".as_ptr(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachip8_load_rom(chip8: Option<&mut Yachip8>, data: *const u8, size: usize) -> bool{
+    let Some(chip8) = chip8 else { return false };
+    chip8.guard(|chip8| {
+        if data.is_null() && size > 0{
+            return Err("rom data is null".to_string());
+        }
+        let rom = if size == 0 { &[][..] } else { slice::from_raw_parts(data, size) };
+        chip8.machine = new_machine(rom, chip8.seed, chip8.platform)?;
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn yachip8_run_cycles(chip8: Option<&mut Yachip8>, cycles: u32) -> bool{
+    let Some(chip8) = chip8 else { return false };
+    chip8.guard(|chip8| {
+        for _ in 0..cycles{
+            chip8.machine.step(&chip8.keys).map_err(|fault| fault.to_string())?;
+        }
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn yachip8_run_frame(chip8: Option<&mut Yachip8>) -> bool{
+    let Some(chip8) = chip8 else { return false };
+    chip8.guard(|chip8| chip8.machine.run_frame(&chip8.keys).map_err(|fault| fault.to_string()))
+}
+
+#[no_mangle]
+pub extern "C" fn yachip8_key_down(chip8: Option<&mut Yachip8>, key: u8){
+    guard((), || if let Some(state) = chip8.and_then(|chip8| chip8.keys.get_mut(key as usize)){
+        *state = 1;
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn yachip8_key_up(chip8: Option<&mut Yachip8>, key: u8){
+    guard((), || if let Some(state) = chip8.and_then(|chip8| chip8.keys.get_mut(key as usize)){
+        *state = 0;
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn yachip8_framebuffer(chip8: Option<&Yachip8>) -> *const u8{
+    match chip8{
+        Some(chip8) => guard(ptr::null(), || chip8.machine.display().framebuffer().as_ptr()),
+        None => ptr::null(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn yachip8_state_size(chip8: Option<&Yachip8>) -> usize{
+    chip8.map(|chip8| guard(0, || chip8.machine.save_state().len())).unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachip8_serialize(chip8: Option<&mut Yachip8>, buffer: *mut u8, size: usize) -> bool{
+    let Some(chip8) = chip8 else { return false };
+    chip8.guard(|chip8| {
+        let state = chip8.machine.save_state();
+        if buffer.is_null() || size < state.len(){
+            return Err(format!("save state needs {} bytes", state.len()));
+        }
+        ptr::copy_nonoverlapping(state.as_ptr(), buffer, state.len());
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachip8_deserialize(chip8: Option<&mut Yachip8>, data: *const u8, size: usize) -> bool{
+    let Some(chip8) = chip8 else { return false };
+    chip8.guard(|chip8| {
+        if data.is_null(){
+            return Err("save state is null".to_string());
+        }
+        chip8.machine.load_state(slice::from_raw_parts(data, size))
+    })
+}
@@ -0,0 +1,77 @@
+use std::ffi::CStr;
+use std::fs;
+use std::ptr;
+use std::slice;
+
+use yachip_8::machine::Machine;
+use yachip_8::platform::Platform;
+use yachip_8::quirks::Quirks;
+use yachip_8::timing::Timing;
+use yachip_8::CYCLES_PER_FRAME;
+use yachip_8_capi::*;
+
+const IBM_LOGO: &str = "../roms/IBM Logo.ch8";
+const COUNTER: [u8; 10] = [0xC0, 0xFF, 0x61, 0x05, 0xF1, 0x15, 0x72, 0x01, 0x12, 0x00];
+
+fn framebuffer(chip8: *mut Yachip8) -> Vec<u8> {
+    let pixels = yachip8_framebuffer(unsafe { chip8.as_ref() });
+    unsafe { slice::from_raw_parts(pixels, YACHIP8_SCREEN_WIDTH * YACHIP8_SCREEN_HEIGHT) }.to_vec()
+}
+
+#[test]
+fn runs_ibm_logo() {
+    let rom = fs::read(IBM_LOGO).unwrap();
+    unsafe {
+        let chip8 = yachip8_create(ptr::null(), 0);
+        assert!(yachip8_load_rom(chip8.as_mut(), rom.as_ptr(), rom.len()));
+        for _ in 0..60 {
+            assert!(yachip8_run_frame(chip8.as_mut()));
+        }
+        let mut machine = Machine::new(&rom, 0, Platform::Chip8, Quirks::default(), Timing::Fixed(CYCLES_PER_FRAME)).unwrap();
+        for _ in 0..60 {
+            machine.run_frame(&[0; 16]).unwrap();
+        }
+        assert_eq!(framebuffer(chip8), machine.display().framebuffer());
+        yachip8_destroy(chip8);
+    }
+}
+
+#[test]
+fn serialize_round_trips() {
+    unsafe {
+        let chip8 = yachip8_create(c"chip8".as_ptr(), 7);
+        assert!(yachip8_load_rom(chip8.as_mut(), COUNTER.as_ptr(), COUNTER.len()));
+        yachip8_key_down(chip8.as_mut(), 0xF);
+        assert!(yachip8_run_cycles(chip8.as_mut(), 3));
+        let mut state = vec![0; yachip8_state_size(chip8.as_ref())];
+        assert!(yachip8_serialize(chip8.as_mut(), state.as_mut_ptr(), state.len()));
+        assert!(yachip8_run_frame(chip8.as_mut()));
+        let mut expected = vec![0; state.len()];
+        assert!(yachip8_serialize(chip8.as_mut(), expected.as_mut_ptr(), expected.len()));
+
+        assert!(yachip8_deserialize(chip8.as_mut(), state.as_ptr(), state.len()));
+        assert!(yachip8_run_frame(chip8.as_mut()));
+        let mut after = vec![0; state.len()];
+        assert!(yachip8_serialize(chip8.as_mut(), after.as_mut_ptr(), after.len()));
+        assert_eq!(after, expected);
+
+        assert!(!yachip8_deserialize(chip8.as_mut(), state.as_ptr(), 3));
+        let error = CStr::from_ptr(yachip8_last_error(chip8.as_ref()));
+        assert_eq!(error.to_str().unwrap(), "save state is truncated");
+        yachip8_key_up(chip8.as_mut(), 0xF);
+        yachip8_destroy(chip8);
+    }
+}
+
+#[test]
+fn rejects_bad_input() {
+    unsafe {
+        assert!(yachip8_create(c"gameboy".as_ptr(), 0).is_null());
+        let chip8 = yachip8_create(ptr::null(), 0);
+        let rom = vec![0; 0x1000];
+        assert!(!yachip8_load_rom(chip8.as_mut(), rom.as_ptr(), rom.len()));
+        assert!(!CStr::from_ptr(yachip8_last_error(chip8.as_ref())).to_bytes().is_empty());
+        assert!(!yachip8_run_frame(None));
+        yachip8_destroy(chip8);
+    }
+}
@@ -0,0 +1,391 @@
+#![allow(clippy::missing_safety_doc)]
+
+use std::os::raw::{c_char, c_uint, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use sha1_smol::Sha1;
+
+use yachip_8::audio::{SquareWave, SAMPLE_RATE};
+use yachip_8::unwind::catch;
+use yachip_8::database::Database;
+use yachip_8::machine::Machine;
+use yachip_8::palette::Palette;
+use yachip_8::platform::Platform;
+use yachip_8::quirks::Quirks;
+use yachip_8::timing::Timing;
+use yachip_8::{CYCLES_PER_FRAME, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+pub mod ffi;
+
+use ffi::*;
+
+const FPS: f64 = 60.0;
+const SAMPLES_PER_FRAME: usize = SAMPLE_RATE as usize / 60;
+const MAX_WIDTH: c_uint = 128;
+const MAX_HEIGHT: c_uint = 64;
+
+const JOYPAD_KEYS: [(c_uint, u8, &str); 16] = [
+    (RETRO_DEVICE_ID_JOYPAD_UP, 0x2, "Key 2\0"),
+    (RETRO_DEVICE_ID_JOYPAD_DOWN, 0x8, "Key 8\0"),
+    (RETRO_DEVICE_ID_JOYPAD_LEFT, 0x4, "Key 4\0"),
+    (RETRO_DEVICE_ID_JOYPAD_RIGHT, 0x6, "Key 6\0"),
+    (RETRO_DEVICE_ID_JOYPAD_A, 0x5, "Key 5\0"),
+    (RETRO_DEVICE_ID_JOYPAD_B, 0x0, "Key 0\0"),
+    (RETRO_DEVICE_ID_JOYPAD_X, 0xE, "Key E\0"),
+    (RETRO_DEVICE_ID_JOYPAD_Y, 0xF, "Key F\0"),
+    (RETRO_DEVICE_ID_JOYPAD_L, 0x1, "Key 1\0"),
+    (RETRO_DEVICE_ID_JOYPAD_R, 0x3, "Key 3\0"),
+    (RETRO_DEVICE_ID_JOYPAD_L2, 0x7, "Key 7\0"),
+    (RETRO_DEVICE_ID_JOYPAD_R2, 0x9, "Key 9\0"),
+    (RETRO_DEVICE_ID_JOYPAD_SELECT, 0xC, "Key C\0"),
+    (RETRO_DEVICE_ID_JOYPAD_START, 0xD, "Key D\0"),
+    (RETRO_DEVICE_ID_JOYPAD_L3, 0xB, "Key B\0"),
+    (RETRO_DEVICE_ID_JOYPAD_R3, 0xA, "Key A\0"),
+];
+
+const KEYBOARD_KEYS: [(c_uint, u8); 16] = [
+    (b'0' as c_uint, 0x0), (b'1' as c_uint, 0x1), (b'2' as c_uint, 0x2), (b'3' as c_uint, 0x3),
+    (b'4' as c_uint, 0x4), (b'5' as c_uint, 0x5), (b'6' as c_uint, 0x6), (b'7' as c_uint, 0x7),
+    (b'8' as c_uint, 0x8), (b'9' as c_uint, 0x9), (b'a' as c_uint, 0xA), (b'b' as c_uint, 0xB),
+    (b'c' as c_uint, 0xC), (b'd' as c_uint, 0xD), (b'e' as c_uint, 0xE), (b'f' as c_uint, 0xF),
+];
+
+#[derive(Clone, Copy)]
+struct Callbacks {
+    environment: Option<RetroEnvironment>,
+    video_refresh: Option<RetroVideoRefresh>,
+    audio_sample_batch: Option<RetroAudioSampleBatch>,
+    input_poll: Option<RetroInputPoll>,
+    input_state: Option<RetroInputState>,
+}
+
+struct Core {
+    machine: Machine,
+    rom: Vec<u8>,
+    seed: u64,
+    platform: Platform,
+    quirks: Quirks,
+    timing: Timing,
+    palette: Palette,
+    joypad: [(c_uint, u8); 16],
+    wave: SquareWave,
+    halted: bool,
+    frame: Vec<u32>,
+    samples: Vec<i16>,
+}
+
+static CALLBACKS: Mutex<Callbacks> = Mutex::new(Callbacks {
+    environment: None,
+    video_refresh: None,
+    audio_sample_batch: None,
+    input_poll: None,
+    input_state: None,
+});
+static CORE: Mutex<Option<Core>> = Mutex::new(None);
+
+fn callbacks() -> Callbacks {
+    *CALLBACKS.lock().unwrap()
+}
+
+impl Core {
+    fn load(rom: &[u8]) -> Result<Core, String> {
+        let info = Database::bundled().lookup(&Sha1::from(rom).hexdigest());
+        let platform = info.as_ref().and_then(|info| info.platform).unwrap_or(Platform::Chip8);
+        let quirks = Quirks {
+            display_wait: info.as_ref().and_then(|info| info.display_wait).unwrap_or(false),
+            ..Quirks::default()
+        };
+        let timing = Timing::Fixed(info.as_ref().and_then(|info| info.tickrate).unwrap_or(CYCLES_PER_FRAME));
+
+        let mut joypad = JOYPAD_KEYS.map(|(id, key, _)| (id, key));
+        for (name, key) in info.iter().flat_map(|info| &info.keys) {
+            let id = match name.as_str() {
+                "up" => RETRO_DEVICE_ID_JOYPAD_UP,
+                "down" => RETRO_DEVICE_ID_JOYPAD_DOWN,
+                "left" => RETRO_DEVICE_ID_JOYPAD_LEFT,
+                "right" => RETRO_DEVICE_ID_JOYPAD_RIGHT,
+                "a" => RETRO_DEVICE_ID_JOYPAD_A,
+                "b" => RETRO_DEVICE_ID_JOYPAD_B,
+                _ => continue,
+            };
+            if let Some(binding) = joypad.iter_mut().find(|(bound, _)| *bound == id) {
+                binding.1 = key & 0xF;
+            }
+        }
+
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_nanos() as u64).unwrap_or(0);
+        Ok(Core {
+            machine: Machine::new(rom, seed, platform, quirks, timing)?,
+            rom: rom.to_vec(),
+            seed,
+            platform,
+            quirks,
+            timing,
+            palette: info.as_ref().and_then(|info| info.palette()).unwrap_or_default(),
+            joypad,
+            wave: SquareWave::new(SAMPLE_RATE),
+            halted: false,
+            frame: vec![0; SCREEN_WIDTH * SCREEN_HEIGHT],
+            samples: vec![0; SAMPLES_PER_FRAME * 2],
+        })
+    }
+
+    fn reset(&mut self) {
+        self.machine = Machine::new(&self.rom, self.seed, self.platform, self.quirks, self.timing).unwrap();
+        self.halted = false;
+    }
+
+    fn keys(joypad: &[(c_uint, u8); 16], input_state: RetroInputState) -> [u8; 16] {
+        let mut keys = [0; 16];
+        for &(id, key) in joypad {
+            if unsafe { input_state(0, RETRO_DEVICE_JOYPAD, 0, id) } != 0 {
+                keys[key as usize] = 1;
+            }
+        }
+        for &(id, key) in &KEYBOARD_KEYS {
+            if unsafe { input_state(0, RETRO_DEVICE_KEYBOARD, 0, id) } != 0 {
+                keys[key as usize] = 1;
+            }
+        }
+        keys
+    }
+
+    fn run(&mut self, keys: &[u8; 16]) {
+        if !self.halted {
+            if let Err(error) = catch(|| self.machine.run_frame(keys).map_err(|fault| fault.to_string())) {
+                eprintln!("yachip_8: {}", error);
+                self.halted = true;
+            }
+        }
+
+        for (pixel, &value) in self.frame.iter_mut().zip(self.machine.display().framebuffer()) {
+            let [r, g, b] = self.palette.color(value);
+            *pixel = u32::from_be_bytes([0, r, g, b]);
+        }
+
+        let tone = !self.halted && self.machine.timer().read_sound_timer() > 0;
+        for frame in self.samples.chunks_mut(2) {
+            let sample = if tone { (self.wave.next().unwrap() * i16::MAX as f32) as i16 } else { 0 };
+            frame.fill(sample);
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_api_version() -> c_uint {
+    RETRO_API_VERSION
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_environment(callback: RetroEnvironment) {
+    CALLBACKS.lock().unwrap().environment = Some(callback);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_video_refresh(callback: RetroVideoRefresh) {
+    CALLBACKS.lock().unwrap().video_refresh = Some(callback);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample(_callback: RetroAudioSample) {}
+
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample_batch(callback: RetroAudioSampleBatch) {
+    CALLBACKS.lock().unwrap().audio_sample_batch = Some(callback);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_poll(callback: RetroInputPoll) {
+    CALLBACKS.lock().unwrap().input_poll = Some(callback);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_state(callback: RetroInputState) {
+    CALLBACKS.lock().unwrap().input_state = Some(callback);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_init() {}
+
+#[no_mangle]
+pub extern "C" fn retro_deinit() {
+    *CORE.lock().unwrap() = None;
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_info(info: *mut RetroSystemInfo) {
+    *info = RetroSystemInfo {
+        library_name: c"yachip_8".as_ptr(),
+        library_version: concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char,
+        valid_extensions: c"ch8|c8|c8x|sc8|xo8".as_ptr(),
+        need_fullpath: false,
+        block_extract: false,
+    };
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_av_info(info: *mut RetroSystemAvInfo) {
+    *info = RetroSystemAvInfo {
+        geometry: RetroGameGeometry {
+            base_width: SCREEN_WIDTH as c_uint,
+            base_height: SCREEN_HEIGHT as c_uint,
+            max_width: MAX_WIDTH,
+            max_height: MAX_HEIGHT,
+            aspect_ratio: SCREEN_WIDTH as f32 / SCREEN_HEIGHT as f32,
+        },
+        timing: RetroSystemTiming {
+            fps: FPS,
+            sample_rate: SAMPLE_RATE as f64,
+        },
+    };
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_controller_port_device(_port: c_uint, _device: c_uint) {}
+
+#[no_mangle]
+pub extern "C" fn retro_reset() {
+    if let Some(core) = CORE.lock().unwrap().as_mut() {
+        core.reset();
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_run() {
+    let callbacks = callbacks();
+    if let Some(input_poll) = callbacks.input_poll {
+        unsafe { input_poll() };
+    }
+    let Some(joypad) = CORE.lock().unwrap().as_ref().map(|core| core.joypad) else { return };
+    let keys = match callbacks.input_state {
+        Some(input_state) => Core::keys(&joypad, input_state),
+        None => [0; 16],
+    };
+    let Some((frame, samples)) = CORE.lock().unwrap().as_mut().map(|core| {
+        core.run(&keys);
+        (core.frame.clone(), core.samples.clone())
+    }) else { return };
+
+    if let Some(video_refresh) = callbacks.video_refresh {
+        unsafe {
+            video_refresh(frame.as_ptr() as *const c_void, SCREEN_WIDTH as c_uint, SCREEN_HEIGHT as c_uint, SCREEN_WIDTH * 4);
+        }
+    }
+    if let Some(audio_sample_batch) = callbacks.audio_sample_batch {
+        unsafe {
+            audio_sample_batch(samples.as_ptr(), SAMPLES_PER_FRAME);
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_serialize_size() -> usize {
+    CORE.lock().unwrap().as_ref().map_or(0, |core| core.machine.save_state().len())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_serialize(data: *mut c_void, size: usize) -> bool {
+    let Some(state) = CORE.lock().unwrap().as_ref().map(|core| core.machine.save_state()) else { return false };
+    if data.is_null() || size < state.len() {
+        return false;
+    }
+    ptr::copy_nonoverlapping(state.as_ptr(), data as *mut u8, state.len());
+    true
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_unserialize(data: *const c_void, size: usize) -> bool {
+    if data.is_null() {
+        return false;
+    }
+    let state = slice::from_raw_parts(data as *const u8, size);
+    match CORE.lock().unwrap().as_mut() {
+        Some(core) => match panic::catch_unwind(AssertUnwindSafe(|| core.machine.load_state(state))) {
+            Ok(Ok(())) => {
+                core.halted = false;
+                true
+            },
+            Ok(Err(error)) => {
+                eprintln!("yachip_8: {}", error);
+                false
+            },
+            Err(_) => {
+                eprintln!("yachip_8: loading the save state panicked");
+                core.halted = true;
+                false
+            },
+        },
+        None => false,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_cheat_reset() {}
+
+#[no_mangle]
+pub extern "C" fn retro_cheat_set(_index: c_uint, _enabled: bool, _code: *const c_char) {}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_load_game(game: *const RetroGameInfo) -> bool {
+    if game.is_null() || (*game).data.is_null() {
+        return false;
+    }
+    let callbacks = callbacks();
+    if let Some(environment) = callbacks.environment {
+        let mut format = RETRO_PIXEL_FORMAT_XRGB8888;
+        if !environment(RETRO_ENVIRONMENT_SET_PIXEL_FORMAT, &mut format as *mut c_uint as *mut c_void) {
+            eprintln!("yachip_8: frontend does not support XRGB8888");
+            return false;
+        }
+        let mut descriptors: Vec<RetroInputDescriptor> = JOYPAD_KEYS.iter().map(|&(id, _, description)| RetroInputDescriptor {
+            port: 0,
+            device: RETRO_DEVICE_JOYPAD,
+            index: 0,
+            id,
+            description: description.as_ptr() as *const c_char,
+        }).collect();
+        descriptors.push(RetroInputDescriptor { port: 0, device: 0, index: 0, id: 0, description: ptr::null() });
+        environment(RETRO_ENVIRONMENT_SET_INPUT_DESCRIPTORS, descriptors.as_mut_ptr() as *mut c_void);
+    }
+
+    let rom = slice::from_raw_parts((*game).data as *const u8, (*game).size);
+    match Core::load(rom) {
+        Ok(core) => {
+            *CORE.lock().unwrap() = Some(core);
+            true
+        },
+        Err(error) => {
+            eprintln!("yachip_8: {}", error);
+            false
+        },
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_load_game_special(_game_type: c_uint, _info: *const RetroGameInfo, _num_info: usize) -> bool {
+    false
+}
+
+#[no_mangle]
+pub extern "C" fn retro_unload_game() {
+    *CORE.lock().unwrap() = None;
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_region() -> c_uint {
+    RETRO_REGION_NTSC
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_memory_data(_id: c_uint) -> *mut c_void {
+    ptr::null_mut()
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_memory_size(_id: c_uint) -> usize {
+    0
+}
@@ -0,0 +1,129 @@
+use std::env;
+use std::ffi::c_void;
+use std::fs;
+use std::os::raw::c_uint;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use libloading::{Library, Symbol};
+
+use yachip_8::machine::Machine;
+use yachip_8::platform::Platform;
+use yachip_8::quirks::Quirks;
+use yachip_8::timing::Timing;
+use yachip_8::CYCLES_PER_FRAME;
+use yachip_8_libretro::ffi::*;
+
+const IBM_LOGO: &str = "../roms/IBM Logo.ch8";
+
+static FRAME: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+static PIXEL_FORMAT: Mutex<Option<c_uint>> = Mutex::new(None);
+static SERIALIZE_SIZE: Mutex<Option<extern "C" fn() -> usize>> = Mutex::new(None);
+
+unsafe extern "C" fn environment(cmd: c_uint, data: *mut c_void) -> bool {
+    if cmd == 10 {
+        *PIXEL_FORMAT.lock().unwrap() = Some(*(data as *const c_uint));
+    }
+    true
+}
+
+unsafe extern "C" fn video_refresh(data: *const c_void, width: c_uint, height: c_uint, pitch: usize) {
+    assert_eq!(pitch, width as usize * 4);
+    if let Some(serialize_size) = *SERIALIZE_SIZE.lock().unwrap() {
+        assert!(serialize_size() > 0);
+    }
+    let pixels = std::slice::from_raw_parts(data as *const u32, (width * height) as usize);
+    *FRAME.lock().unwrap() = pixels.to_vec();
+}
+
+unsafe extern "C" fn audio_sample_batch(_data: *const i16, frames: usize) -> usize {
+    frames
+}
+
+unsafe extern "C" fn input_poll() {}
+
+unsafe extern "C" fn input_state(_port: c_uint, _device: c_uint, _index: c_uint, _id: c_uint) -> i16 {
+    0
+}
+
+fn core_path() -> PathBuf {
+    let exe = env::current_exe().unwrap();
+    exe.parent().unwrap().join(format!("{}yachip_8_libretro{}", env::consts::DLL_PREFIX, env::consts::DLL_SUFFIX))
+}
+
+fn lit_pixels() -> Vec<bool> {
+    FRAME.lock().unwrap().iter().map(|&pixel| pixel != 0).collect()
+}
+
+#[test]
+fn runs_ibm_logo_like_the_core() {
+    let rom = fs::read(IBM_LOGO).unwrap();
+    let core = unsafe { Library::new(core_path()) }.unwrap();
+    macro_rules! load {
+        ($($name:ident: $type:ty),* $(,)?) => {
+            $(let $name: Symbol<$type> = unsafe { core.get(stringify!($name).as_bytes()) }.unwrap();)*
+        };
+    }
+    load! {
+        retro_set_environment: extern "C" fn(RetroEnvironment),
+        retro_set_video_refresh: extern "C" fn(RetroVideoRefresh),
+        retro_set_audio_sample_batch: extern "C" fn(RetroAudioSampleBatch),
+        retro_set_input_poll: extern "C" fn(RetroInputPoll),
+        retro_set_input_state: extern "C" fn(RetroInputState),
+        retro_init: extern "C" fn(),
+        retro_deinit: extern "C" fn(),
+        retro_load_game: unsafe extern "C" fn(*const RetroGameInfo) -> bool,
+        retro_unload_game: extern "C" fn(),
+        retro_run: extern "C" fn(),
+        retro_reset: extern "C" fn(),
+        retro_serialize_size: extern "C" fn() -> usize,
+        retro_serialize: unsafe extern "C" fn(*mut c_void, usize) -> bool,
+        retro_unserialize: unsafe extern "C" fn(*const c_void, usize) -> bool,
+    }
+
+    retro_set_environment(environment);
+    retro_set_video_refresh(video_refresh);
+    retro_set_audio_sample_batch(audio_sample_batch);
+    retro_set_input_poll(input_poll);
+    retro_set_input_state(input_state);
+    retro_init();
+    *SERIALIZE_SIZE.lock().unwrap() = Some(*retro_serialize_size);
+
+    let game = RetroGameInfo {
+        path: std::ptr::null(),
+        data: rom.as_ptr() as *const c_void,
+        size: rom.len(),
+        meta: std::ptr::null(),
+    };
+    assert!(unsafe { retro_load_game(&game) });
+    assert_eq!(*PIXEL_FORMAT.lock().unwrap(), Some(1));
+
+    for _ in 0..30 {
+        retro_run();
+    }
+    let mut state = vec![0u8; retro_serialize_size()];
+    assert!(unsafe { retro_serialize(state.as_mut_ptr() as *mut c_void, state.len()) });
+    for _ in 0..30 {
+        retro_run();
+    }
+
+    let mut machine = Machine::new(&rom, 0, Platform::Chip8, Quirks::default(), Timing::Fixed(CYCLES_PER_FRAME)).unwrap();
+    for _ in 0..60 {
+        machine.run_frame(&[0; 16]).unwrap();
+    }
+    let expected: Vec<bool> = machine.display().framebuffer().iter().map(|&pixel| pixel != 0).collect();
+    assert_eq!(lit_pixels(), expected);
+
+    retro_reset();
+    retro_run();
+    assert_ne!(lit_pixels(), expected);
+
+    assert!(unsafe { retro_unserialize(state.as_ptr() as *const c_void, state.len()) });
+    for _ in 0..30 {
+        retro_run();
+    }
+    assert_eq!(lit_pixels(), expected);
+
+    retro_unload_game();
+    retro_deinit();
+}
@@ -0,0 +1,194 @@
+use pyo3::exceptions::{PyIndexError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use yachip_8::environment::{Environment as RomEnvironment, Rules};
+use yachip_8::machine::Machine;
+use yachip_8::movie::keys_from_mask;
+use yachip_8::platform::Platform;
+use yachip_8::quirks::Quirks;
+use yachip_8::timing::Timing;
+use yachip_8::{CYCLES_PER_FRAME, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+#[pyclass]
+struct Chip8{
+    machine: Machine,
+    seed: u64,
+    platform: Platform,
+    quirks: Quirks,
+    timing: Timing,
+    keys: [u8; 16],
+}
+
+#[pymethods]
+impl Chip8{
+    #[new]
+    #[pyo3(signature = (platform = "chip8", seed = 0, tickrate = None, display_wait = false))]
+    fn new(platform: &str, seed: u64, tickrate: Option<u32>, display_wait: bool) -> PyResult<Chip8>{
+        let platform = Platform::named(platform).ok_or_else(|| PyValueError::new_err(format!("unknown platform {}", platform)))?;
+        let quirks = Quirks{
+            display_wait,
+            ..Quirks::default()
+        };
+        let timing = Timing::Fixed(tickrate.unwrap_or(CYCLES_PER_FRAME));
+        Ok(Chip8{
+            machine: Machine::new(&[], seed, platform, quirks, timing).map_err(PyValueError::new_err)?,
+            seed,
+            platform,
+            quirks,
+            timing,
+            keys: [0; 16],
+        })
+    }
+
+    fn load_rom(&mut self, rom: &[u8]) -> PyResult<()>{
+        self.machine = Machine::new(rom, self.seed, self.platform, self.quirks, self.timing).map_err(PyValueError::new_err)?;
+        Ok(())
+    }
+
+    fn step(&mut self) -> PyResult<()>{
+        self.machine.step(&self.keys).map_err(|fault| PyRuntimeError::new_err(fault.to_string()))?;
+        Ok(())
+    }
+
+    #[pyo3(signature = (frames = 1))]
+    fn run_frame(&mut self, py: Python<'_>, frames: u32) -> PyResult<()>{
+        let (machine, keys) = (&mut self.machine, &self.keys);
+        py.detach(|| (0..frames).try_for_each(|_| machine.run_frame(keys)))
+            .map_err(|fault| PyRuntimeError::new_err(fault.to_string()))
+    }
+
+    fn set_keys(&mut self, keys: u16){
+        self.keys = keys_from_mask(keys);
+    }
+
+    fn set_key(&mut self, key: usize, pressed: bool) -> PyResult<()>{
+        let state = self.keys.get_mut(key).ok_or_else(|| PyIndexError::new_err(format!("no key {:#x}", key)))?;
+        *state = pressed as u8;
+        Ok(())
+    }
+
+    fn framebuffer<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes>{
+        PyBytes::new(py, self.machine.display().framebuffer())
+    }
+
+    fn framebuffer_array<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>>{
+        let numpy = py.import("numpy")?;
+        numpy.call_method1("frombuffer", (self.framebuffer(py), "uint8"))?
+            .call_method1("reshape", ((SCREEN_HEIGHT, SCREEN_WIDTH),))?
+            .call_method0("copy")
+    }
+
+    #[getter]
+    fn registers(&self) -> Vec<u8>{
+        self.machine.cpu().registers().to_vec()
+    }
+
+    #[getter]
+    fn index(&self) -> u16{
+        self.machine.cpu().index_register()
+    }
+
+    #[getter]
+    fn program_counter(&self) -> u16{
+        self.machine.cpu().program_counter()
+    }
+
+    #[getter]
+    fn delay_timer(&self) -> u8{
+        self.machine.timer().read_delay_timer()
+    }
+
+    #[getter]
+    fn sound_timer(&self) -> u8{
+        self.machine.timer().read_sound_timer()
+    }
+
+    #[pyo3(signature = (address, length = 1))]
+    fn read_memory<'py>(&self, py: Python<'py>, address: usize, length: usize) -> PyResult<Bound<'py, PyBytes>>{
+        self.check_range(address, length)?;
+        let ram = self.machine.ram();
+        let data: Vec<u8> = (address..address + length).map(|address| ram.read(address as u16)).collect();
+        Ok(PyBytes::new(py, &data))
+    }
+
+    fn write_memory(&mut self, address: usize, data: &[u8]) -> PyResult<()>{
+        self.check_range(address, data.len())?;
+        let ram = self.machine.ram_mut();
+        for (offset, &byte) in data.iter().enumerate(){
+            ram.write((address + offset) as u16, byte);
+        }
+        Ok(())
+    }
+
+    fn save_state<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes>{
+        PyBytes::new(py, &self.machine.save_state())
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> PyResult<()>{
+        self.machine.load_state(state).map_err(PyValueError::new_err)
+    }
+}
+
+impl Chip8{
+    fn check_range(&self, address: usize, length: usize) -> PyResult<()>{
+        let size = self.machine.ram().size();
+        if address.checked_add(length).is_none_or(|end| end > size){
+            return Err(PyIndexError::new_err(format!("{} bytes at {:#x} are past the end of {} bytes of memory", length, address, size)));
+        }
+        Ok(())
+    }
+}
+
+#[pyclass]
+struct Environment{
+    environment: RomEnvironment,
+}
+
+#[pymethods]
+impl Environment{
+    #[new]
+    #[pyo3(signature = (rom, rules = "{}", frames_per_step = 4, seed = 0, platform = "chip8", tickrate = None, display_wait = false))]
+    fn new(rom: &[u8], rules: &str, frames_per_step: u32, seed: u64, platform: &str, tickrate: Option<u32>, display_wait: bool) -> PyResult<Environment>{
+        let platform = Platform::named(platform).ok_or_else(|| PyValueError::new_err(format!("unknown platform {}", platform)))?;
+        let quirks = Quirks{
+            display_wait,
+            ..Quirks::default()
+        };
+        let timing = Timing::Fixed(tickrate.unwrap_or(CYCLES_PER_FRAME));
+        let rules = Rules::parse(rules).map_err(PyValueError::new_err)?;
+        Ok(Environment{
+            environment: RomEnvironment::new(rom, seed, platform, quirks, timing, rules, frames_per_step).map_err(PyValueError::new_err)?,
+        })
+    }
+
+    #[pyo3(signature = (seed = None))]
+    fn reset<'py>(&mut self, py: Python<'py>, seed: Option<u64>) -> Bound<'py, PyBytes>{
+        if let Some(seed) = seed{
+            self.environment.set_seed(seed);
+        }
+        PyBytes::new(py, self.environment.reset())
+    }
+
+    fn step<'py>(&mut self, py: Python<'py>, action: u16) -> (Bound<'py, PyBytes>, f64, bool){
+        let step = self.environment.step(action);
+        (PyBytes::new(py, step.observation), step.reward, step.done)
+    }
+
+    #[getter]
+    fn frame(&self) -> u32{
+        self.environment.frame()
+    }
+
+    #[getter]
+    fn fault(&self) -> Option<String>{
+        self.environment.fault().map(|fault| fault.to_string())
+    }
+}
+
+#[pymodule]
+fn yachip8(module: &Bound<'_, PyModule>) -> PyResult<()>{
+    module.add_class::<Chip8>()?;
+    module.add_class::<Environment>()?;
+    Ok(())
+}
@@ -0,0 +1,81 @@
+import hashlib
+from pathlib import Path
+
+import pytest
+
+from yachip8 import Chip8, Environment
+
+IBM_LOGO = (Path(__file__).parents[2] / "roms" / "IBM Logo.ch8").read_bytes()
+COUNTER = bytes([0xC0, 0xFF, 0x61, 0x05, 0xF1, 0x15, 0x72, 0x01, 0x12, 0x00])
+
+
+def test_ibm_logo_matches_headless_run():
+    chip8 = Chip8()
+    chip8.load_rom(IBM_LOGO)
+    chip8.run_frame(60)
+    assert len(chip8.framebuffer()) == 64 * 32
+    assert hashlib.sha1(chip8.framebuffer()).hexdigest() == "d4598c296d5884a621d3fb2bc9461a308710fcfa"
+
+
+def test_step_runs_one_instruction():
+    chip8 = Chip8(seed=7)
+    chip8.load_rom(COUNTER)
+    assert chip8.program_counter == 0x200
+    chip8.step()
+    chip8.step()
+    assert chip8.program_counter == 0x204
+    assert chip8.registers[1] == 5
+
+
+def test_memory_and_keys():
+    chip8 = Chip8()
+    chip8.load_rom(COUNTER)
+    assert chip8.read_memory(0x200, 2) == bytes([0xC0, 0xFF])
+    chip8.write_memory(0x300, b"\x12\x34")
+    assert chip8.read_memory(0x300, 2) == b"\x12\x34"
+    with pytest.raises(IndexError):
+        chip8.read_memory(0xFFF, 2)
+    with pytest.raises(IndexError):
+        chip8.read_memory(2**64 - 1, 2)
+    chip8.set_keys(0b1000_0000_0000_0001)
+    chip8.set_key(0xF, False)
+    with pytest.raises(IndexError):
+        chip8.set_key(16, True)
+
+
+def test_save_state_round_trips():
+    chip8 = Chip8(seed=7)
+    chip8.load_rom(COUNTER)
+    chip8.run_frame()
+    state = chip8.save_state()
+    chip8.run_frame(5)
+    expected = chip8.save_state()
+    chip8.load_state(state)
+    chip8.run_frame(5)
+    assert chip8.save_state() == expected
+    with pytest.raises(ValueError):
+        chip8.load_state(b"not a state")
+
+
+def test_framebuffer_array():
+    numpy = pytest.importorskip("numpy")
+    chip8 = Chip8()
+    chip8.load_rom(IBM_LOGO)
+    chip8.run_frame(60)
+    array = chip8.framebuffer_array()
+    assert array.shape == (32, 64)
+    assert array.dtype == numpy.uint8
+    assert array.tobytes() == chip8.framebuffer()
+
+
+def test_environment_rewards_bcd_score():
+    rom = bytes([0x71, 0x01, 0xA3, 0x00, 0xF1, 0x33, 0x12, 0x00])
+    rules = '{"reward": [{"bcd": "0x300"}], "done": [{"register": "V1", "equals": 10}]}'
+    environment = Environment(rom, rules, frames_per_step=2, tickrate=4)
+    assert len(environment.reset()) == 64 * 32
+    results = [environment.step(0) for _ in range(5)]
+    assert [(reward, done) for _, reward, done in results] == [(2.0, False)] * 4 + [(2.0, True)]
+    assert environment.frame == 10
+    assert environment.fault is None
+    environment.reset(seed=1)
+    assert environment.frame == 0
@@ -0,0 +1,111 @@
+use std::f32::consts::PI;
+
+use crate::timer::Timer;
+
+pub const SAMPLE_RATE: i32 = 44100;
+pub const TONE_FREQUENCY: f32 = 440.0;
+pub const AMPLITUDE: f32 = 0.25;
+
+const FRAMES_PER_SECOND: i32 = 60;
+
+pub trait AudioSink{
+    fn play(&mut self, tone: bool);
+
+    fn update(&mut self, timer: &Timer){
+        self.play(timer.read_sound_timer() > 0);
+    }
+}
+
+pub struct SquareWave{
+    phase_inc: f32,
+    phase: f32,
+    volume: f32,
+}
+
+impl SquareWave{
+    pub fn new(sample_rate: i32) -> SquareWave{
+        SquareWave{
+            phase_inc: 2.0 * PI * TONE_FREQUENCY / sample_rate as f32,
+            phase: 0.0,
+            volume: AMPLITUDE,
+        }
+    }
+}
+
+impl Iterator for SquareWave{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32>{
+        self.phase = (self.phase + self.phase_inc) % (2.0 * PI);
+
+        let value = if self.phase < PI {
+            self.volume
+        } else {
+            -self.volume
+        };
+
+        Some(value)
+    }
+}
+
+pub struct NullSink;
+
+impl AudioSink for NullSink{
+    fn play(&mut self, _tone: bool){}
+}
+
+pub struct BufferSink{
+    wave: SquareWave,
+    samples: Vec<f32>,
+}
+
+impl Default for BufferSink{
+    fn default() -> BufferSink{
+        BufferSink::new()
+    }
+}
+
+impl BufferSink{
+    pub fn new() -> BufferSink{
+        BufferSink{
+            wave: SquareWave::new(SAMPLE_RATE),
+            samples: Vec::new(),
+        }
+    }
+
+    pub fn samples(&self) -> &[f32]{
+        &self.samples
+    }
+}
+
+impl AudioSink for BufferSink{
+    fn play(&mut self, tone: bool){
+        let count = (SAMPLE_RATE / FRAMES_PER_SECOND) as usize;
+        if tone{
+            self.samples.extend(self.wave.by_ref().take(count));
+        }else{
+            self.samples.extend(std::iter::repeat_n(0.0, count));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffer_sink_plays_while_sound_timer_runs() {
+        let mut timer = Timer::new();
+        let mut sink = BufferSink::new();
+        timer.set_sound_timer(2);
+        for _ in 0..3 {
+            sink.update(&timer);
+            timer.tick();
+        }
+
+        let frame = (SAMPLE_RATE / FRAMES_PER_SECOND) as usize;
+        assert_eq!(sink.samples().len(), frame * 3);
+        assert!(sink.samples()[..frame * 2].iter().all(|sample| sample.abs() == AMPLITUDE));
+        assert!(sink.samples()[frame * 2..].iter().all(|&sample| sample == 0.0));
+    }
+}
@@ -0,0 +1,216 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use serde_json::{json, Value};
+use sha1_smol::Sha1;
+
+use crate::database::Database;
+use crate::machine::Machine;
+use crate::movie::Movie;
+use crate::platform::Platform;
+use crate::quirks::Quirks;
+use crate::rom::Rom;
+use crate::timing::Timing;
+use crate::unwind::catch;
+use crate::CYCLES_PER_FRAME;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Job{
+    pub rom: PathBuf,
+    pub entry: Option<String>,
+    pub seed: Option<u64>,
+    pub frames: Option<u64>,
+    pub movie: Option<PathBuf>,
+    pub platform: Option<Platform>,
+    pub tickrate: Option<u32>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Outcome{
+    pub seed: u64,
+    pub frames: u64,
+    pub sha1: Option<String>,
+    pub error: Option<String>,
+}
+
+pub fn parse_manifest(json: &str, directory: &Path) -> Result<Vec<Job>, String>{
+    let manifest: Value = serde_json::from_str(json).map_err(|e| format!("manifest: {}", e))?;
+    let runs = manifest.as_array().ok_or("manifest: expected a list of runs")?;
+    runs.iter().enumerate().map(|(index, run)| {
+        let error = |message: &str| format!("manifest: run {}: {}", index, message);
+        let rom = run["rom"].as_str().ok_or_else(|| error("missing rom"))?;
+        let number = |name: &str| match &run[name]{
+            Value::Null => Ok(None),
+            value => value.as_u64().map(Some).ok_or_else(|| error(&format!("invalid {}", name))),
+        };
+        let platform = match &run["platform"]{
+            Value::Null => None,
+            value => Some(value.as_str().and_then(Platform::named).ok_or_else(|| error("unknown platform"))?),
+        };
+        Ok(Job{
+            rom: directory.join(rom),
+            entry: run["entry"].as_str().map(str::to_string),
+            seed: number("seed")?,
+            frames: number("frames")?,
+            movie: run["movie"].as_str().map(|movie| directory.join(movie)),
+            platform,
+            tickrate: number("tickrate")?
+                .map(|tickrate| u32::try_from(tickrate).ok().filter(|&tickrate| tickrate > 0).ok_or_else(|| error("invalid tickrate")))
+                .transpose()?,
+        })
+    }).collect()
+}
+
+pub fn run(jobs: &[Job], threads: usize) -> Vec<Outcome>{
+    let database = Database::bundled();
+    parallel(jobs, threads, |job| run_job(job, &database))
+}
+
+pub fn parallel<T: Sync, R: Send, F: Fn(&T) -> R + Sync>(items: &[T], threads: usize, f: F) -> Vec<R>{
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..items.len()).map(|_| None).collect::<Vec<Option<R>>>());
+    thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, items.len().max(1)){
+            scope.spawn(|| {
+                loop{
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else { break };
+                    let result = f(item);
+                    results.lock().unwrap()[index] = Some(result);
+                }
+            });
+        }
+    });
+    results.into_inner().unwrap().into_iter().map(Option::unwrap).collect()
+}
+
+pub fn run_job(job: &Job, database: &Database) -> Outcome{
+    let mut outcome = Outcome{
+        seed: job.seed.unwrap_or(0),
+        frames: 0,
+        sha1: None,
+        error: None,
+    };
+    if let Err(error) = catch(|| run_frames(job, database, &mut outcome)){
+        outcome.error = Some(error);
+    }
+    outcome
+}
+
+fn run_frames(job: &Job, database: &Database, outcome: &mut Outcome) -> Result<(), String>{
+    let mut movie = job.movie.as_deref().map(Movie::load).transpose()?;
+    if job.frames.is_none() && movie.is_none(){
+        return Err("needs frames or a movie".to_string());
+    }
+    if let (None, Some(movie)) = (job.seed, &movie){
+        outcome.seed = movie.seed();
+    }
+
+    let rom = Rom::load(&job.rom, |names| job.entry.as_ref().and_then(|entry| names.iter().position(|name| name == entry)))?;
+    let info = rom.info.or_else(|| database.lookup(&Sha1::from(&rom.data).hexdigest()));
+    let platform = job.platform
+        .or(info.as_ref().and_then(|info| info.platform))
+        .unwrap_or(rom.platform);
+    let quirks = Quirks{
+        display_wait: info.as_ref().and_then(|info| info.display_wait).unwrap_or(false),
+        ..Quirks::default()
+    };
+    let tickrate = job.tickrate
+        .or(info.as_ref().and_then(|info| info.tickrate))
+        .unwrap_or(CYCLES_PER_FRAME);
+    let mut machine = Machine::new(&rom.data, outcome.seed, platform, quirks, Timing::Fixed(tickrate))
+        .map_err(|error| format!("{}: {}", job.rom.display(), error))?;
+
+    let mut result = Ok(());
+    while job.frames.is_none_or(|frames| outcome.frames < frames){
+        let keys = match &mut movie{
+            Some(movie) => match movie.next_frame(){
+                Some(keys) => keys,
+                None => break,
+            },
+            None => [0; 16],
+        };
+        if let Err(fault) = machine.run_frame(&keys){
+            result = Err(fault.to_string());
+            break;
+        }
+        outcome.frames += 1;
+    }
+    outcome.sha1 = Some(Sha1::from(machine.display().framebuffer()).hexdigest());
+    result
+}
+
+pub fn report(jobs: &[Job], outcomes: &[Outcome]) -> String{
+    let runs: Vec<Value> = jobs.iter().zip(outcomes).map(|(job, outcome)| json!({
+        "rom": job.rom.display().to_string(),
+        "seed": outcome.seed,
+        "frames": outcome.frames,
+        "sha1": outcome.sha1,
+        "error": outcome.error,
+    })).collect();
+    serde_json::to_string_pretty(&runs).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::CPU;
+    use crate::display::Display;
+    use crate::ram::RAM;
+    use crate::timer::Timer;
+
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn machines_can_move_between_threads() {
+        assert_send::<CPU>();
+        assert_send::<RAM>();
+        assert_send::<Display>();
+        assert_send::<Timer>();
+        assert_send::<Machine>();
+    }
+
+    #[test]
+    fn parses_manifest() {
+        let jobs = parse_manifest(r#"[
+            {"rom": "a.ch8", "seed": 3, "frames": 60, "platform": "schip", "tickrate": 20},
+            {"rom": "b.zip", "entry": "b.ch8", "movie": "b.mov"}
+        ]"#, Path::new("runs")).unwrap();
+        assert_eq!(jobs[0], Job {
+            rom: PathBuf::from("runs/a.ch8"),
+            entry: None,
+            seed: Some(3),
+            frames: Some(60),
+            movie: None,
+            platform: Some(Platform::SuperChip),
+            tickrate: Some(20),
+        });
+        assert_eq!(jobs[1].movie, Some(PathBuf::from("runs/b.mov")));
+        assert_eq!(jobs[1].entry.as_deref(), Some("b.ch8"));
+        assert!(parse_manifest(r#"[{"seed": 1}]"#, Path::new("")).is_err());
+        assert!(parse_manifest(r#"[{"rom": "a.ch8", "platform": "nes"}]"#, Path::new("")).is_err());
+        for tickrate in ["0", "4294967296", "-1", "1.5"] {
+            let manifest = format!(r#"[{{"rom": "a.ch8", "tickrate": {}}}]"#, tickrate);
+            assert_eq!(parse_manifest(&manifest, Path::new("")), Err("manifest: run 0: invalid tickrate".to_string()));
+        }
+    }
+
+    #[test]
+    fn runs_jobs_in_order_across_threads() {
+        let jobs = parse_manifest(r#"[
+            {"rom": "IBM Logo.ch8", "frames": 60},
+            {"rom": "missing.ch8", "frames": 60},
+            {"rom": "IBM Logo.ch8"},
+            {"rom": "IBM Logo.ch8", "frames": 60, "seed": 9}
+        ]"#, Path::new("roms")).unwrap();
+        let outcomes = run(&jobs, 3);
+        assert_eq!(outcomes.len(), 4);
+        assert_eq!(outcomes[0].sha1.as_deref(), Some("d4598c296d5884a621d3fb2bc9461a308710fcfa"));
+        assert_eq!((outcomes[0].frames, &outcomes[0].error), (60, &None));
+        assert!(outcomes[1].error.as_ref().unwrap().contains("missing.ch8"));
+        assert_eq!(outcomes[2].error.as_deref(), Some("needs frames or a movie"));
+        assert_eq!((outcomes[3].seed, &outcomes[3].sha1), (9, &outcomes[0].sha1));
+    }
+}
@@ -0,0 +1,227 @@
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+
+use crate::display::Display;
+use crate::platform::Platform;
+use crate::rom::{self, Rom};
+
+const VISIBLE_ROWS: usize = 5;
+const ROW_HEIGHT: usize = 6;
+const GLYPH_WIDTH: usize = 4;
+const NAME_LENGTH: usize = 15;
+
+pub struct Browser{
+    directory: PathBuf,
+    archive: bool,
+    entries: Vec<PathBuf>,
+    selected: usize,
+}
+
+impl Browser{
+    pub fn new(directory: &Path) -> Result<Browser, String>{
+        let directory = directory.canonicalize().map_err(|e| format!("{}: {}", directory.display(), e))?;
+        if rom::is_archive(&directory){
+            return Browser::archive(directory);
+        }
+        let mut directories = Vec::new();
+        let mut roms = Vec::new();
+        for entry in read_dir(&directory).map_err(|e| format!("{}: {}", directory.display(), e))?{
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path.is_dir(){
+                directories.push(path);
+            }else if is_rom(&path){
+                roms.push(path);
+            }
+        }
+        directories.sort();
+        roms.sort();
+
+        let mut entries: Vec<PathBuf> = directory.parent().map(Path::to_path_buf).into_iter().collect();
+        entries.extend(directories);
+        entries.extend(roms);
+        Ok(Browser{
+            directory,
+            archive: false,
+            entries,
+            selected: 0,
+        })
+    }
+
+    fn archive(path: PathBuf) -> Result<Browser, String>{
+        let mut entries: Vec<PathBuf> = path.parent().map(Path::to_path_buf).into_iter().collect();
+        entries.extend(Rom::entries(&path)?.into_iter().map(PathBuf::from));
+        Ok(Browser{
+            directory: path,
+            archive: true,
+            entries,
+            selected: 0,
+        })
+    }
+
+    pub fn directory(&self) -> &Path{
+        &self.directory
+    }
+
+    pub fn up(&mut self){
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn down(&mut self){
+        self.selected = (self.selected + 1).min(self.entries.len().saturating_sub(1));
+    }
+
+    pub fn selected(&self) -> Option<&Path>{
+        self.entries.get(self.selected).map(PathBuf::as_path)
+    }
+
+    pub fn selected_entry(&self) -> Option<(&Path, &str)>{
+        match self.selected(){
+            Some(entry) if self.archive && Some(entry) != self.directory.parent() => Some((&self.directory, entry.to_str()?)),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self, index: usize) -> String{
+        let path = &self.entries[index];
+        if Some(path.as_path()) == self.directory.parent(){
+            return "../".to_string();
+        }
+        if self.archive{
+            return path.to_string_lossy().to_string();
+        }
+        let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        if path.is_dir(){
+            format!("{}/", name)
+        }else{
+            name
+        }
+    }
+
+    pub fn draw(&self, display: &mut Display){
+        display.clear();
+        let first = self.selected.saturating_sub(VISIBLE_ROWS - 1);
+        for (row, index) in (first..self.entries.len()).take(VISIBLE_ROWS).enumerate(){
+            let y = 1 + row * ROW_HEIGHT;
+            if index == self.selected{
+                display.draw(0, y, &sprite('>'));
+            }
+            for (column, character) in self.name(index).chars().take(NAME_LENGTH).enumerate(){
+                display.draw((column + 1) * GLYPH_WIDTH, y, &sprite(character));
+            }
+        }
+    }
+}
+
+pub fn is_rom(path: &Path) -> bool{
+    let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or("");
+    Platform::from_extension(extension).is_some() || rom::is_archive(path)
+}
+
+fn sprite(character: char) -> [u8; 5]{
+    glyph(character).map(|row| row << 5)
+}
+
+fn glyph(character: char) -> [u8; 5]{
+    match character.to_ascii_uppercase(){
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '>' => [0b100, 0b010, 0b001, 0b010, 0b100],
+        '(' | '[' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        ')' | ']' => [0b110, 0b001, 0b001, 0b001, 0b110],
+        _ => [0b111, 0b001, 0b011, 0b000, 0b010],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+    use zip::write::{FileOptions, ZipWriter};
+
+    #[test]
+    fn lists_directories_then_roms() {
+        let directory = std::env::temp_dir().join(format!("yachip_8_browser_{}", std::process::id()));
+        fs::create_dir_all(directory.join("games")).unwrap();
+        let directory = directory.canonicalize().unwrap();
+        for file in ["pong.ch8", "notes.txt", "collection.zip", "blinky.sc8"] {
+            fs::write(directory.join(file), [0]).unwrap();
+        }
+
+        let mut browser = Browser::new(&directory).unwrap();
+        let names: Vec<String> = (0..browser.entries.len()).map(|index| browser.name(index)).collect();
+        assert_eq!(names, ["../", "games/", "blinky.sc8", "collection.zip", "pong.ch8"]);
+
+        browser.up();
+        assert_eq!(browser.selected(), directory.parent());
+        for _ in 0..10 {
+            browser.down();
+        }
+        assert_eq!(browser.selected(), Some(directory.join("pong.ch8").as_path()));
+
+        let mut display = Display::new();
+        browser.draw(&mut display);
+        assert!(display.framebuffer().contains(&1));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn lists_roms_inside_an_archive() {
+        let path = std::env::temp_dir().join(format!("yachip_8_browser_{}.zip", std::process::id()));
+        let mut writer = ZipWriter::new(fs::File::create(&path).unwrap());
+        for file in ["b.ch8", "readme.txt", "a.ch8"] {
+            writer.start_file(file, FileOptions::default()).unwrap();
+            writer.write_all(&[0]).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let mut browser = Browser::new(&path).unwrap();
+        let names: Vec<String> = (0..browser.entries.len()).map(|index| browser.name(index)).collect();
+        assert_eq!(names, ["../", "a.ch8", "b.ch8"]);
+        assert_eq!(browser.selected_entry(), None);
+        browser.down();
+        browser.down();
+        assert_eq!(browser.selected_entry(), Some((path.canonicalize().unwrap().as_path(), "b.ch8")));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,210 @@
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::browser::is_rom;
+use crate::fault::Fault;
+use crate::machine::Machine;
+use crate::platform::Platform;
+use crate::quirks::Quirks;
+use crate::rom::Rom;
+use crate::timing::Timing;
+use crate::unwind::catch;
+use crate::CYCLES_PER_FRAME;
+
+pub struct Profile{
+    pub name: &'static str,
+    pub platform: Platform,
+    pub quirks: Quirks,
+    pub timing: Timing,
+}
+
+pub const PROFILES: [Profile; 5] = [
+    Profile{
+        name: "chip8",
+        platform: Platform::Chip8,
+        quirks: Quirks{ display_wait: false, index_overflow: false, strict_memory: false },
+        timing: Timing::Fixed(CYCLES_PER_FRAME),
+    },
+    Profile{
+        name: "vip",
+        platform: Platform::Chip8,
+        quirks: Quirks{ display_wait: true, index_overflow: false, strict_memory: false },
+        timing: Timing::Vip,
+    },
+    Profile{
+        name: "amiga",
+        platform: Platform::Chip8,
+        quirks: Quirks{ display_wait: false, index_overflow: true, strict_memory: false },
+        timing: Timing::Fixed(CYCLES_PER_FRAME),
+    },
+    Profile{
+        name: "strict",
+        platform: Platform::Chip8,
+        quirks: Quirks{ display_wait: false, index_overflow: false, strict_memory: true },
+        timing: Timing::Fixed(CYCLES_PER_FRAME),
+    },
+    Profile{
+        name: "xo-chip",
+        platform: Platform::XoChip,
+        quirks: Quirks{ display_wait: false, index_overflow: false, strict_memory: false },
+        timing: Timing::Fixed(CYCLES_PER_FRAME),
+    },
+];
+
+pub struct Report{
+    pub rom: PathBuf,
+    pub profile: &'static str,
+    pub frames: u64,
+    pub crash: Option<String>,
+    pub fault: Option<Fault>,
+    pub loop_at: Option<u16>,
+    pub waits_for_key: bool,
+    pub screen_changes: u64,
+    pub lit_pixels: usize,
+}
+
+impl Report{
+    pub fn status(&self) -> &'static str{
+        match (&self.crash, &self.fault, self.loop_at){
+            (Some(_), _, _) => "crash",
+            (_, Some(Fault::UnknownOpcode { .. }), _) => "unknown-opcode",
+            (_, Some(Fault::StackOverflow { .. } | Fault::StackUnderflow { .. }), _) => "stack-fault",
+            (_, Some(Fault::OutOfBounds { .. }), _) => "out-of-bounds",
+            (_, _, Some(_)) => "loop",
+            _ if self.screen_changes == 0 => "blank",
+            _ => "ok",
+        }
+    }
+
+    pub fn summary(&self) -> String{
+        let outcome = match (&self.crash, &self.fault, self.loop_at){
+            (Some(crash), _, _) => return format!("crashed: {}", crash),
+            (_, Some(fault), _) => format!("{} after {} frames", fault, self.frames),
+            (_, _, Some(address)) => format!("loops at {:#05x} from frame {}", address, self.frames),
+            _ if self.waits_for_key => format!("ran {} frames, waiting for a key", self.frames),
+            _ => format!("ran {} frames", self.frames),
+        };
+        match self.screen_changes{
+            0 => format!("{}, screen stayed blank", outcome),
+            changes => format!("{}, screen changed on {} frames, {} pixels lit", outcome, changes, self.lit_pixels),
+        }
+    }
+
+    pub fn to_json(&self) -> Value{
+        json!({
+            "rom": self.rom.display().to_string(),
+            "profile": self.profile,
+            "status": self.status(),
+            "frames": self.frames,
+            "error": self.crash.clone().or(self.fault.as_ref().map(Fault::to_string)),
+            "loop_at": self.loop_at,
+            "waits_for_key": self.waits_for_key,
+            "screen_changes": self.screen_changes,
+            "lit_pixels": self.lit_pixels,
+        })
+    }
+}
+
+pub fn roms(directory: &Path) -> Result<Vec<PathBuf>, String>{
+    let mut roms = Vec::new();
+    for entry in read_dir(directory).map_err(|e| format!("{}: {}", directory.display(), e))?{
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if path.is_file() && is_rom(&path){
+            roms.push(path);
+        }
+    }
+    roms.sort();
+    Ok(roms)
+}
+
+pub fn check(rom: &Path, profile: &'static Profile, frames: u64) -> Report{
+    let mut report = Report{
+        rom: rom.to_path_buf(),
+        profile: profile.name,
+        frames: 0,
+        crash: None,
+        fault: None,
+        loop_at: None,
+        waits_for_key: false,
+        screen_changes: 0,
+        lit_pixels: 0,
+    };
+    if let Err(error) = catch(|| run(rom, profile, frames, &mut report)){
+        report.crash = Some(error);
+    }
+    report
+}
+
+fn run(rom: &Path, profile: &Profile, frames: u64, report: &mut Report) -> Result<(), String>{
+    let rom = Rom::load(rom, |_| Some(0))?;
+    let mut machine = Machine::new(&rom.data, 0, profile.platform, profile.quirks, profile.timing)?;
+    let mut previous = machine.display().framebuffer().to_vec();
+    while report.frames < frames{
+        if let Err(fault) = machine.run_frame(&[0; 16]){
+            report.fault = Some(fault);
+            break;
+        }
+        report.frames += 1;
+
+        let framebuffer = machine.display().framebuffer();
+        if framebuffer != previous.as_slice(){
+            report.screen_changes += 1;
+            previous.copy_from_slice(framebuffer);
+        }
+        let program_counter = machine.cpu().program_counter();
+        let opcode = (machine.ram().read(program_counter) as u16) << 8 | machine.ram().read(program_counter.wrapping_add(1)) as u16;
+        if opcode == 0x1000 | program_counter{
+            report.loop_at = Some(program_counter);
+            break;
+        }
+        report.waits_for_key = opcode & 0xF0FF == 0xF00A;
+    }
+    report.lit_pixels = previous.iter().filter(|&&pixel| pixel != 0).count();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    fn check_rom(name: &str, rom: &[u8], profile: &'static Profile) -> Report {
+        let path = env::temp_dir().join(format!("yachip_8_compat_{}_{}.ch8", process::id(), name));
+        fs::write(&path, rom).unwrap();
+        let report = check(&path, profile, 60);
+        fs::remove_file(&path).unwrap();
+        report
+    }
+
+    #[test]
+    fn finds_loops_and_screen_activity() {
+        let report = check(Path::new("roms/IBM Logo.ch8"), &PROFILES[0], 600);
+        assert_eq!(report.status(), "loop");
+        assert_eq!(report.loop_at, Some(0x228));
+        assert!(report.frames < 10);
+        assert_eq!(report.lit_pixels, 208);
+        assert!(report.summary().starts_with("loops at 0x228"));
+    }
+
+    #[test]
+    fn reports_faults() {
+        assert_eq!(check_rom("unknown", &[0x00, 0x00, 0x80, 0x0F], &PROFILES[0]).status(), "unknown-opcode");
+        assert_eq!(check_rom("underflow", &[0x00, 0xEE], &PROFILES[0]).status(), "stack-fault");
+        assert_eq!(check_rom("overflow", &[0x22, 0x00], &PROFILES[0]).status(), "stack-fault");
+
+        let out_of_bounds = [0xAF, 0xFF, 0xF1, 0x65, 0x12, 0x04];
+        assert_eq!(check_rom("bounds", &out_of_bounds, &PROFILES[0]).status(), "loop");
+        assert_eq!(check_rom("bounds_strict", &out_of_bounds, &PROFILES[3]).status(), "out-of-bounds");
+
+        let blank = check_rom("blank", &[0x70, 0x01, 0x12, 0x00], &PROFILES[0]);
+        assert_eq!((blank.status(), blank.frames), ("blank", 60));
+        let waiting = check_rom("waiting", &[0xF0, 0x0A, 0x12, 0x00], &PROFILES[0]);
+        assert!(waiting.waits_for_key);
+        assert_eq!(waiting.summary(), "ran 60 frames, waiting for a key, screen stayed blank");
+        assert!(check(Path::new("roms/missing.ch8"), &PROFILES[0], 60).crash.is_some());
+    }
+}
@@ -0,0 +1,314 @@
+use std::path::PathBuf;
+use std::thread;
+
+use yachip_8::palette::{self, Palette, PALETTES};
+use yachip_8::platform::{Platform, PLATFORMS};
+use yachip_8::quirks::Quirks;
+use yachip_8::timing::Timing;
+use yachip_8::graphics::Protocol;
+use yachip_8::CYCLES_PER_FRAME;
+
+#[cfg(feature = "terminal")]
+use crate::terminal::Output;
+use crate::DEFAULT_SCALE;
+
+#[derive(Clone, Copy)]
+pub enum Scaling {
+    Integer,
+    Fractional,
+}
+
+pub const USAGE: &str = "usage: yachip_8 [options] [rom]
+       yachip_8 batch [--frames <n>] [--threads <n>] <manifest>
+       yachip_8 compat [--frames <n>] [--threads <n>] [--json] [directory]
+
+rom can be a .zip archive or - to read from stdin
+
+options:
+    --headless          run without a window
+    --terminal          play in the terminal, drawing pixels with half-block characters
+    --braille           play in the terminal, drawing pixels with braille dots
+    --sixel, --kitty    play in the terminal, drawing the screen as sixel or kitty graphics at --scale
+    --dump <format>     print the last screen as sixel or kitty graphics after --headless
+    --frames <n>        stop after n frames
+    --record <movie>    record keypad input to a movie file
+    --play <movie>      play back keypad input from a movie file
+    --seed <n>          seed the random number generator used by CXNN
+    --palette <name>    colour palette: classic, phosphor, amber, gameboy, high-contrast
+    --fg <rrggbb>       foreground colour
+    --bg <rrggbb>       background colour
+    --scale <n>         initial window size as a multiple of the screen resolution
+    --fractional        scale to fill the window instead of by whole pixels
+    --persistence <n>   fade pixels out over n frames to reduce flicker
+    --scanlines         darken every other line like a CRT
+    --display-wait      stall after DXYN until the next frame like the COSMAC VIP,
+    --no-display-wait   or don't, overriding the rom database
+    --timing <mode>     fixed (instructions per frame) or vip (COSMAC VIP machine cycles)
+    --tickrate <n>      instructions per frame with fixed timing (default 10)
+    --platform <name>   chip8, chip8x, eti-660 (starts at 0x600), schip or xo-chip (64K memory),
+                        picked from the rom extension (.ch8, .c8x, .sc8, .xo8) when not given,
+                        only the memory size and start address change between platforms
+    --index-overflow    FX1E sets VF when I goes past 0xFFF like the Amiga interpreter
+    --strict            stop with an error on memory accesses past the end of RAM
+
+settings not given on the command line are taken from the rom database when the rom is known
+
+keys:
+    P                   switch to the next palette
+    F11, Alt+Enter      toggle fullscreen
+    Esc                 open the rom browser, or go back to the running rom
+    Up, Down, Enter     pick a rom in the browser
+    Q, Ctrl+C           quit the terminal frontend
+
+dropping a rom on the window loads it";
+
+pub const BATCH_USAGE: &str = "usage: yachip_8 batch [--frames <n>] [--threads <n>] <manifest>
+
+runs every rom in the manifest headlessly on a pool of threads and prints the results as json
+
+the manifest is a json list of runs like {\"rom\": \"game.ch8\", \"seed\": 1, \"frames\": 600},
+runs can also give a \"movie\" to play back, a \"platform\", a \"tickrate\"
+and the \"entry\" to run from a zip holding several roms, paths are relative to the manifest
+
+options:
+    --frames <n>        frames to run when a run gives no frames or movie
+    --threads <n>       worker threads (default: one per cpu)";
+
+pub const COMPAT_USAGE: &str = "usage: yachip_8 compat [--frames <n>] [--threads <n>] [--json] [directory]
+
+runs every rom in the directory (default roms/) headlessly without input under each quirk profile
+(chip8, vip, amiga, strict, xo-chip) and reports crashes, unknown opcodes, stack faults,
+out of bounds accesses with strict, jumps to the same address and whether the screen changed
+
+options:
+    --frames <n>        frames to run each rom for (default 600)
+    --threads <n>       worker threads (default: one per cpu)
+    --json              print the results as json";
+
+pub struct Config{
+    pub rom: Option<PathBuf>,
+    pub headless: bool,
+    #[cfg(feature = "terminal")]
+    pub terminal: Option<Output>,
+    pub dump: Option<Protocol>,
+    pub frames: Option<u64>,
+    pub record: Option<PathBuf>,
+    pub play: Option<PathBuf>,
+    pub seed: Option<u64>,
+    pub palette: Option<Palette>,
+    pub foreground: Option<[u8; 3]>,
+    pub background: Option<[u8; 3]>,
+    pub scale: u32,
+    pub scaling: Scaling,
+    pub persistence: u8,
+    pub scanlines: bool,
+    pub quirks: Quirks,
+    pub display_wait: Option<bool>,
+    pub timing: Timing,
+    pub tickrate: Option<u32>,
+    pub platform: Option<Platform>,
+}
+
+impl Config{
+    pub fn new() -> Config{
+        Config{
+            rom: None,
+            headless: false,
+            #[cfg(feature = "terminal")]
+            terminal: None,
+            dump: None,
+            frames: None,
+            record: None,
+            play: None,
+            seed: None,
+            palette: None,
+            foreground: None,
+            background: None,
+            scale: DEFAULT_SCALE,
+            scaling: Scaling::Integer,
+            persistence: 0,
+            scanlines: false,
+            quirks: Quirks::default(),
+            display_wait: None,
+            timing: Timing::Fixed(CYCLES_PER_FRAME),
+            tickrate: None,
+            platform: None,
+        }
+    }
+
+    pub fn from_args<I: Iterator<Item = String>>(mut args: I) -> Result<Config, String>{
+        let mut config = Config::new();
+
+        while let Some(arg) = args.next(){
+            match arg.as_str(){
+                "--headless" => config.headless = true,
+                #[cfg(feature = "terminal")]
+                "--terminal" => config.terminal = Some(Output::HalfBlocks),
+                #[cfg(feature = "terminal")]
+                "--braille" => config.terminal = Some(Output::Braille),
+                #[cfg(feature = "terminal")]
+                "--sixel" => config.terminal = Some(Output::Graphics(Protocol::Sixel)),
+                #[cfg(feature = "terminal")]
+                "--kitty" => config.terminal = Some(Output::Graphics(Protocol::Kitty)),
+                "--dump" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.dump = Some(Protocol::named(&value).ok_or_else(|| format!("unknown graphics format: {} (expected sixel or kitty)", value))?);
+                },
+                "--frames" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.frames = Some(value.parse().map_err(|_| format!("invalid frame count: {}", value))?);
+                },
+                "--record" => config.record = Some(PathBuf::from(next_value(&mut args, &arg)?)),
+                "--play" => config.play = Some(PathBuf::from(next_value(&mut args, &arg)?)),
+                "--seed" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.seed = Some(value.parse().map_err(|_| format!("invalid seed: {}", value))?);
+                },
+                "--palette" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.palette = Some(Palette::named(&value).ok_or_else(|| {
+                        let names: Vec<&str> = PALETTES.iter().map(|(name, _)| *name).collect();
+                        format!("unknown palette: {} (expected one of {})", value, names.join(", "))
+                    })?);
+                },
+                "--fg" => config.foreground = Some(palette::parse_color(&next_value(&mut args, &arg)?)?),
+                "--bg" => config.background = Some(palette::parse_color(&next_value(&mut args, &arg)?)?),
+                "--scale" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.scale = match value.parse() {
+                        Ok(scale) if scale > 0 => scale,
+                        _ => return Err(format!("invalid scale: {}", value)),
+                    };
+                },
+                "--fractional" => config.scaling = Scaling::Fractional,
+                "--persistence" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.persistence = value.parse().map_err(|_| format!("invalid persistence: {}", value))?;
+                },
+                "--scanlines" => config.scanlines = true,
+                "--display-wait" => config.display_wait = Some(true),
+                "--no-display-wait" => config.display_wait = Some(false),
+                "--timing" => {
+                    config.timing = match next_value(&mut args, &arg)?.as_str(){
+                        "fixed" => Timing::Fixed(CYCLES_PER_FRAME),
+                        "vip" => Timing::Vip,
+                        value => return Err(format!("unknown timing mode: {}", value)),
+                    };
+                },
+                "--tickrate" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.tickrate = match value.parse() {
+                        Ok(tickrate) if tickrate > 0 => Some(tickrate),
+                        _ => return Err(format!("invalid tickrate: {}", value)),
+                    };
+                },
+                "--platform" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.platform = Some(Platform::named(&value).ok_or_else(|| {
+                        let names: Vec<&str> = PLATFORMS.iter().map(|(name, _)| *name).collect();
+                        format!("unknown platform: {} (expected one of {})", value, names.join(", "))
+                    })?);
+                },
+                "--index-overflow" => config.quirks.index_overflow = true,
+                "--strict" => config.quirks.strict_memory = true,
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                _ if arg.starts_with("--") => return Err(format!("unknown option: {}\n\n{}", arg, USAGE)),
+                _ => config.rom = Some(PathBuf::from(arg)),
+            }
+        }
+
+        if config.record.is_some() && config.play.is_some(){
+            return Err("--record and --play cannot be used together".to_string());
+        }
+        if config.seed.is_some() && config.play.is_some(){
+            return Err("--seed cannot be used with --play, the movie stores its own seed".to_string());
+        }
+        if config.rom.is_none() && (config.headless || config.record.is_some() || config.play.is_some()){
+            return Err("--headless, --record and --play need a rom".to_string());
+        }
+        if config.dump.is_some() && !config.headless{
+            return Err("--dump needs --headless".to_string());
+        }
+        if config.headless && config.frames.is_none() && config.play.is_none(){
+            return Err("--headless needs --frames or --play".to_string());
+        }
+        Ok(config)
+    }
+}
+
+pub struct BatchConfig{
+    pub manifest: PathBuf,
+    pub frames: Option<u64>,
+    pub threads: usize,
+}
+
+impl BatchConfig{
+    pub fn from_args<I: Iterator<Item = String>>(mut args: I) -> Result<BatchConfig, String>{
+        let mut manifest = None;
+        let mut frames = None;
+        let mut threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+
+        while let Some(arg) = args.next(){
+            match arg.as_str(){
+                "--frames" => {
+                    let value = next_value(&mut args, &arg)?;
+                    frames = Some(value.parse().map_err(|_| format!("invalid frame count: {}", value))?);
+                },
+                "--threads" => threads = parse_threads(&next_value(&mut args, &arg)?)?,
+                "-h" | "--help" => return Err(BATCH_USAGE.to_string()),
+                _ if arg.starts_with("--") => return Err(format!("unknown option: {}\n\n{}", arg, BATCH_USAGE)),
+                _ => manifest = Some(PathBuf::from(arg)),
+            }
+        }
+
+        Ok(BatchConfig{
+            manifest: manifest.ok_or_else(|| BATCH_USAGE.to_string())?,
+            frames,
+            threads,
+        })
+    }
+}
+
+pub struct CompatConfig{
+    pub directory: PathBuf,
+    pub frames: u64,
+    pub threads: usize,
+    pub json: bool,
+}
+
+impl CompatConfig{
+    pub fn from_args<I: Iterator<Item = String>>(mut args: I) -> Result<CompatConfig, String>{
+        let mut config = CompatConfig{
+            directory: PathBuf::from("roms/"),
+            frames: 600,
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+            json: false,
+        };
+
+        while let Some(arg) = args.next(){
+            match arg.as_str(){
+                "--frames" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.frames = value.parse().map_err(|_| format!("invalid frame count: {}", value))?;
+                },
+                "--threads" => config.threads = parse_threads(&next_value(&mut args, &arg)?)?,
+                "--json" => config.json = true,
+                "-h" | "--help" => return Err(COMPAT_USAGE.to_string()),
+                _ if arg.starts_with("--") => return Err(format!("unknown option: {}\n\n{}", arg, COMPAT_USAGE)),
+                _ => config.directory = PathBuf::from(arg),
+            }
+        }
+        Ok(config)
+    }
+}
+
+fn parse_threads(value: &str) -> Result<usize, String>{
+    match value.parse(){
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(format!("invalid thread count: {}", value)),
+    }
+}
+
+fn next_value<I: Iterator<Item = String>>(args: &mut I, option: &str) -> Result<String, String>{
+    args.next().ok_or_else(|| format!("missing value for {}", option))
+}
@@ -1,13 +1,13 @@
-use rand::random;
+use std::mem;
 
 use crate::ram::RAM;
 use crate::display::Display;
-use crate::input::Input;
 use crate::timer::Timer;
-use crate::sound::Sound;
-
-use sdl2::render::Canvas;
-use sdl2::video::Window;
+use crate::rng::Rng;
+use crate::quirks::Quirks;
+use crate::timing;
+use crate::fault::Fault;
+use crate::state::{StateReader, StateWriter};
 
 use crate::REGISTER_COUNT;
 use crate::STACK_SIZE;
@@ -18,37 +18,149 @@ pub struct CPU{
     program_counter: u16,
     stack: [u16; STACK_SIZE],
     stack_pointer: u8,
-    draw_flag: bool,
+    rng: Rng,
+    quirks: Quirks,
+    waiting_for_vblank: bool,
+    out_of_bounds: Option<u32>,
+    program_counter_wrapped: bool,
+    fault: Option<Fault>,
 }
 
 impl CPU{
-    pub fn new() -> CPU{
+    pub fn new(seed: u64, quirks: Quirks) -> CPU{
         CPU{
             registers: [0; REGISTER_COUNT],
             index_register: 0,
             program_counter: 0x200,
             stack: [0; STACK_SIZE],
             stack_pointer: 0,
-            draw_flag: false,
+            rng: Rng::new(seed),
+            quirks,
+            waiting_for_vblank: false,
+            out_of_bounds: None,
+            program_counter_wrapped: false,
+            fault: None,
         }
     }
 
-    pub fn cycle(&mut self, ram: &mut RAM, display: &mut Display, input: &mut Input, timer: &mut Timer, sound: &mut Sound, canvas: &mut Canvas<Window>){
+    pub fn set_program_counter(&mut self, address: u16){
+        self.program_counter = address;
+    }
+
+    pub fn vblank(&mut self){
+        self.waiting_for_vblank = false;
+    }
+
+    pub fn waiting_for_vblank(&self) -> bool{
+        self.waiting_for_vblank
+    }
+
+    pub fn quirks(&self) -> Quirks{
+        self.quirks
+    }
+
+    pub fn registers(&self) -> &[u8]{
+        &self.registers
+    }
+
+    pub fn index_register(&self) -> u16{
+        self.index_register
+    }
+
+    pub fn program_counter(&self) -> u16{
+        self.program_counter
+    }
+
+    pub fn save_state(&self, writer: &mut StateWriter){
+        for &register in &self.registers{
+            writer.u8(register);
+        }
+        writer.u16(self.index_register);
+        writer.u16(self.program_counter);
+        for &address in &self.stack{
+            writer.u16(address);
+        }
+        writer.u8(self.stack_pointer);
+        writer.u8(self.waiting_for_vblank as u8);
+        self.rng.save_state(writer);
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), String>{
+        for register in self.registers.iter_mut(){
+            *register = reader.u8()?;
+        }
+        self.index_register = reader.u16()?;
+        self.program_counter = reader.u16()?;
+        for address in self.stack.iter_mut(){
+            *address = reader.u16()?;
+        }
+        self.stack_pointer = reader.u8()?;
+        if self.stack_pointer as usize > STACK_SIZE{
+            return Err(format!("save state has an invalid stack pointer {}", self.stack_pointer));
+        }
+        self.waiting_for_vblank = reader.u8()? != 0;
+        self.rng.load_state(reader)
+    }
+
+    pub fn cycle(&mut self, ram: &mut RAM, display: &mut Display, keys: &[u8; 16], timer: &mut Timer) -> Result<u32, Fault>{
+        if self.waiting_for_vblank{
+            return Ok(0);
+        }
+        let program_counter = self.program_counter;
         let opcode = self.fetch_opcode(ram);
-        self.execute_opcode(opcode, ram, display, input, timer, sound, canvas);
+        let x = self.registers[((opcode & 0x0F00) >> 8) as usize];
+        self.execute_opcode(opcode, ram, display, keys, timer);
+        let address = self.program_counter as u32 + if mem::take(&mut self.program_counter_wrapped) { 0x10000 } else { 0 };
+        if self.quirks.strict_memory && address as usize >= ram.size() && self.out_of_bounds.is_none() && self.fault.is_none(){
+            self.out_of_bounds = Some(address);
+        }
+        if let Some(address) = self.out_of_bounds.take(){
+            self.program_counter = program_counter;
+            return Err(Fault::OutOfBounds { program_counter, opcode, address });
+        }
+        if let Some(fault) = self.fault.take(){
+            return Err(fault);
+        }
+        self.program_counter = (address as usize & (ram.size() - 1)) as u16;
+        Ok(timing::vip_cycles(opcode, x, address == program_counter as u32 + 4))
+    }
+
+    fn advance(&mut self, bytes: u16){
+        let (program_counter, wrapped) = self.program_counter.overflowing_add(bytes);
+        self.program_counter = program_counter;
+        self.program_counter_wrapped = wrapped;
+    }
+
+    fn fetch_opcode(&mut self, ram: &RAM) -> u16{
+        let high = self.memory_address(ram, self.program_counter as u32);
+        let low = self.memory_address(ram, self.program_counter as u32 + 1);
+        (ram.read(high) as u16) << 8 | ram.read(low) as u16
+    }
+
+    fn memory_address(&mut self, ram: &RAM, address: u32) -> u16{
+        if self.quirks.strict_memory && address as usize >= ram.size() && self.out_of_bounds.is_none(){
+            self.out_of_bounds = Some(address);
+        }
+        address as u16
+    }
+
+    fn index_address(&mut self, ram: &RAM, offset: usize) -> u16{
+        self.memory_address(ram, self.index_register as u32 + offset as u32)
     }
 
-    fn fetch_opcode(&self, ram: &RAM) -> u16{
-        let opcode = (ram.read(self.program_counter) as u16) << 8 | ram.read(self.program_counter + 1) as u16;
-        opcode
+    fn index_range_in_bounds(&mut self, ram: &RAM, length: usize) -> bool{
+        for offset in 0..length{
+            self.index_address(ram, offset);
+        }
+        self.out_of_bounds.is_none()
     }
 
-    fn execute_opcode(&mut self, opcode: u16, ram: &mut RAM, display: &mut Display, input: &mut Input, timer: &mut Timer, sound: &mut Sound, canvas: &mut Canvas<Window>){
+    fn execute_opcode(&mut self, opcode: u16, ram: &mut RAM, display: &mut Display, keys: &[u8; 16], timer: &mut Timer){
         match opcode & 0xF000{
-            0x0000 => match opcode & 0x000F{
-                0x0000 => self.clear_screen(display),
-                0x000E => self.return_from_subroutine(),
-                _ => println!("Unknown opcode: {:x}", opcode),
+            0x0000 => match opcode{
+                0x00E0 => self.clear_screen(display),
+                0x00EE => self.return_from_subroutine(),
+                _ => self.unknown_opcode(opcode),
             },
             0x1000 => self.jump_to_address(opcode),
             0x2000 => self.call_subroutine(opcode),
@@ -67,43 +179,51 @@ impl CPU{
                 0x0006 => self.shift_right(opcode),
                 0x0007 => self.sub_register_register_reverse(opcode),
                 0x000E => self.shift_left(opcode),
-                _ => println!("Unknown opcode: {:x}", opcode),
+                _ => self.unknown_opcode(opcode),
             },
             0x9000 => self.skip_if_not_equal_register(opcode),
             0xA000 => self.set_index_register(opcode),
             0xB000 => self.jump_to_address_plus_register(opcode),
             0xC000 => self.set_register_random(opcode),
-            0xD000 => self.draw_sprite(opcode, display, ram, canvas),
+            0xD000 => self.draw_sprite(opcode, display, ram),
             0xE000 => match opcode & 0x000F{
-                0x000E => self.skip_if_key_pressed(opcode, input),
-                0x0001 => self.skip_if_key_not_pressed(opcode, input),
-                _ => println!("Unknown opcode: {:x}", opcode),
+                0x000E => self.skip_if_key_pressed(opcode, keys),
+                0x0001 => self.skip_if_key_not_pressed(opcode, keys),
+                _ => self.unknown_opcode(opcode),
             },
             0xF000 => match opcode & 0x00FF{
                 0x0007 => self.set_register_delay_timer(opcode, timer),
-                0x000A => self.wait_for_key_press(opcode, input),
+                0x000A => self.wait_for_key_press(opcode, keys),
                 0x0015 => self.set_delay_timer_register(opcode, timer),
-                0x0018 => self.set_sound_timer_register(opcode, timer, sound),
+                0x0018 => self.set_sound_timer_register(opcode, timer),
                 0x001E => self.add_index_register_register(opcode),
                 0x0029 => self.set_index_register_sprite(opcode),
                 0x0033 => self.store_bcd(opcode, ram),
                 0x0055 => self.store_registers(opcode, ram),
                 0x0065 => self.load_registers(opcode, ram),
-                _ => println!("Unknown opcode: {:x}", opcode),
+                _ => self.unknown_opcode(opcode),
             },
-            _ => println!("Unknown opcode: {:x}", opcode),
+            _ => self.unknown_opcode(opcode),
         }
     }
 
+    fn unknown_opcode(&mut self, opcode: u16){
+        self.fault = Some(Fault::UnknownOpcode { program_counter: self.program_counter, opcode });
+    }
+
     fn clear_screen(&mut self, display: &mut Display){
         display.clear();
-        self.program_counter += 2;
+        self.advance(2);
     }
 
     fn return_from_subroutine(&mut self){
-        self.program_counter = self.stack[self.stack_pointer as usize];
+        if self.stack_pointer == 0{
+            self.fault = Some(Fault::StackUnderflow { program_counter: self.program_counter, opcode: 0x00EE });
+            return;
+        }
         self.stack_pointer -= 1;
-        self.program_counter += 2;
+        self.program_counter = self.stack[self.stack_pointer as usize];
+        self.advance(2);
     }
 
     fn jump_to_address(&mut self, opcode: u16){
@@ -111,8 +231,12 @@ impl CPU{
     }
 
     fn call_subroutine(&mut self, opcode: u16){
-        self.stack_pointer += 1;
+        if self.stack_pointer as usize >= STACK_SIZE{
+            self.fault = Some(Fault::StackOverflow { program_counter: self.program_counter, opcode });
+            return;
+        }
         self.stack[self.stack_pointer as usize] = self.program_counter;
+        self.stack_pointer += 1;
         self.program_counter = opcode & 0x0FFF;
     }
 
@@ -120,9 +244,9 @@ impl CPU{
         let register = ((opcode & 0x0F00) >> 8) as usize;
         let value = (opcode & 0x00FF) as u8;
         if self.registers[register] == value{
-            self.program_counter += 4;
+            self.advance(4);
         }else{
-            self.program_counter += 2;
+            self.advance(2);
         }
     }
 
@@ -130,9 +254,9 @@ impl CPU{
         let register = ((opcode & 0x0F00) >> 8) as usize;
         let value = (opcode & 0x00FF) as u8;
         if self.registers[register] != value{
-            self.program_counter += 4;
+            self.advance(4);
         }else{
-            self.program_counter += 2;
+            self.advance(2);
         }
     }
 
@@ -140,9 +264,9 @@ impl CPU{
         let register1 = ((opcode & 0x0F00) >> 8) as usize;
         let register2 = ((opcode & 0x00F0) >> 4) as usize;
         if self.registers[register1] == self.registers[register2]{
-            self.program_counter += 4;
+            self.advance(4);
         }else{
-            self.program_counter += 2;
+            self.advance(2);
         }
     }
 
@@ -150,98 +274,96 @@ impl CPU{
         let register = ((opcode & 0x0F00) >> 8) as usize;
         let value = (opcode & 0x00FF) as u8;
         self.registers[register] = value;
-        self.program_counter += 2;
+        self.advance(2);
     }
 
     fn add_to_register(&mut self, opcode: u16){
         let register = ((opcode & 0x0F00) >> 8) as usize;
         let value = (opcode & 0x00FF) as u8;
         self.registers[register] = self.registers[register].wrapping_add(value);
-        self.program_counter += 2;
+        self.advance(2);
     }
 
     fn set_register_register(&mut self, opcode: u16){
         let register1 = ((opcode & 0x0F00) >> 8) as usize;
         let register2 = ((opcode & 0x00F0) >> 4) as usize;
         self.registers[register1] = self.registers[register2];
-        self.program_counter += 2;
+        self.advance(2);
     }
 
     fn set_register_or(&mut self, opcode: u16){
         let register1 = ((opcode & 0x0F00) >> 8) as usize;
         let register2 = ((opcode & 0x00F0) >> 4) as usize;
-        self.registers[register1] = self.registers[register1] | self.registers[register2];
-        self.program_counter += 2;
+        self.registers[register1] |= self.registers[register2];
+        self.advance(2);
     }
 
     fn set_register_and(&mut self, opcode: u16){
         let register1 = ((opcode & 0x0F00) >> 8) as usize;
         let register2 = ((opcode & 0x00F0) >> 4) as usize;
-        self.registers[register1] = self.registers[register1] & self.registers[register2];
-        self.program_counter += 2;
+        self.registers[register1] &= self.registers[register2];
+        self.advance(2);
     }
 
     fn set_register_xor(&mut self, opcode: u16){
         let register1 = ((opcode & 0x0F00) >> 8) as usize;
         let register2 = ((opcode & 0x00F0) >> 4) as usize;
-        self.registers[register1] = self.registers[register1] ^ self.registers[register2];
-        self.program_counter += 2;
+        self.registers[register1] ^= self.registers[register2];
+        self.advance(2);
     }
 
     fn add_register_register(&mut self, opcode: u16){
         let register1 = ((opcode & 0x0F00) >> 8) as usize;
         let register2 = ((opcode & 0x00F0) >> 4) as usize;
         let (result, overflow) = self.registers[register1].overflowing_add(self.registers[register2]);
-        self.registers[register1] = result;
-        self.registers[0xF] = if overflow {1} else {0};
-        self.program_counter += 2;
+        self.commit_with_flag(register1, result, overflow as u8);
     }
 
     fn sub_register_register(&mut self, opcode: u16){
         let register1 = ((opcode & 0x0F00) >> 8) as usize;
         let register2 = ((opcode & 0x00F0) >> 4) as usize;
         let (result, overflow) = self.registers[register1].overflowing_sub(self.registers[register2]);
-        self.registers[register1] = result;
-        self.registers[0xF] = if overflow {0} else {1};
-        self.program_counter += 2;
+        self.commit_with_flag(register1, result, !overflow as u8);
     }
 
     fn shift_right(&mut self, opcode: u16){
         let register = ((opcode & 0x0F00) >> 8) as usize;
-        self.registers[0xF] = self.registers[register] & 0x1;
-        self.registers[register] >>= 1;
-        self.program_counter += 2;
+        let value = self.registers[register];
+        self.commit_with_flag(register, value >> 1, value & 0x1);
     }
 
     fn sub_register_register_reverse(&mut self, opcode: u16){
         let register1 = ((opcode & 0x0F00) >> 8) as usize;
         let register2 = ((opcode & 0x00F0) >> 4) as usize;
         let (result, overflow) = self.registers[register2].overflowing_sub(self.registers[register1]);
-        self.registers[register1] = result;
-        self.registers[0xF] = if overflow {0} else {1};
-        self.program_counter += 2;
+        self.commit_with_flag(register1, result, !overflow as u8);
     }
 
     fn shift_left(&mut self, opcode: u16){
         let register = ((opcode & 0x0F00) >> 8) as usize;
-        self.registers[0xF] = self.registers[register] >> 7;
-        self.registers[register] <<= 1;
-        self.program_counter += 2;
+        let value = self.registers[register];
+        self.commit_with_flag(register, value << 1, value >> 7);
+    }
+
+    fn commit_with_flag(&mut self, register: usize, result: u8, flag: u8){
+        self.registers[register] = result;
+        self.registers[0xF] = flag;
+        self.advance(2);
     }
 
     fn skip_if_not_equal_register(&mut self, opcode: u16){
         let register1 = ((opcode & 0x0F00) >> 8) as usize;
         let register2 = ((opcode & 0x00F0) >> 4) as usize;
         if self.registers[register1] != self.registers[register2]{
-            self.program_counter += 4;
+            self.advance(4);
         }else{
-            self.program_counter += 2;
+            self.advance(2);
         }
     }
 
     fn set_index_register(&mut self, opcode: u16){
         self.index_register = opcode & 0x0FFF;
-        self.program_counter += 2;
+        self.advance(2);
     }
 
     fn jump_to_address_plus_register(&mut self, opcode: u16){
@@ -252,118 +374,701 @@ impl CPU{
    fn set_register_random(&mut self, opcode: u16){
         let register = ((opcode & 0x0F00) >> 8) as usize;
         let value = (opcode & 0x00FF) as u8;
-        let random = random::<u8>();
+        let random = self.rng.next_u8();
         self.registers[register] = random & value;
-        self.program_counter += 2;
+        self.advance(2);
     }
 
-   fn draw_sprite(&mut self, opcode: u16, display: &mut Display, ram: &RAM, canvas: &mut Canvas<Window>){
+   fn draw_sprite(&mut self, opcode: u16, display: &mut Display, ram: &RAM){
        let x = self.registers[((opcode & 0x0F00) >> 8) as usize] as usize;
        let y = self.registers[((opcode & 0x00F0) >> 4) as usize] as usize;
        let height = (opcode & 0x000F) as usize;
+       if !self.index_range_in_bounds(ram, height){
+           return;
+       }
        self.registers[0xF] = 0;
        let mut sprite = vec![0; height];
-       for yline in 0..height {
-           sprite[yline] = ram.read(self.index_register + yline as u16);
+       for (yline, row) in sprite.iter_mut().enumerate() {
+           *row = ram.read(self.index_address(ram, yline));
        }
-       let collision = display.draw(x, y, &sprite, canvas);
+       let collision = display.draw(x, y, &sprite);
        self.registers[0xF] = collision as u8;
        display.set_draw_flag(true);
-       self.program_counter += 2;
+       self.waiting_for_vblank = self.quirks.display_wait;
+       self.advance(2);
    }
 
 
-    fn skip_if_key_pressed(&mut self, opcode: u16, input: &mut Input){
+    fn skip_if_key_pressed(&mut self, opcode: u16, keys: &[u8; 16]){
         let register = ((opcode & 0x0F00) >> 8) as usize;
-        if input.get_keys()[self.registers[register] as usize] != 0 {
-            self.program_counter += 4;
+        if keys[(self.registers[register] & 0xF) as usize] != 0 {
+            self.advance(4);
         }else{
-            self.program_counter += 2;
+            self.advance(2);
         }
     }
 
-    fn skip_if_key_not_pressed(&mut self, opcode: u16, input: &mut Input){
+    fn skip_if_key_not_pressed(&mut self, opcode: u16, keys: &[u8; 16]){
         let register = ((opcode & 0x0F00) >> 8) as usize;
-        if input.get_keys()[self.registers[register] as usize] == 0 {
-            self.program_counter += 4;
+        if keys[(self.registers[register] & 0xF) as usize] == 0 {
+            self.advance(4);
         }else{
-            self.program_counter += 2;
+            self.advance(2);
         }
     }
 
     fn set_register_delay_timer(&mut self, opcode: u16, timer: &mut Timer){
         let register = ((opcode & 0x0F00) >> 8) as usize;
         self.registers[register] = timer.read_delay_timer();
-        self.program_counter += 2;
+        self.advance(2);
     }
 
-    fn wait_for_key_press(&mut self, opcode: u16, input: &mut Input) {
+    fn wait_for_key_press(&mut self, opcode: u16, keys: &[u8; 16]) {
         let register = ((opcode & 0x0F00) >> 8) as usize;
-        let mut key_pressed = false;
-        let mut key_value = 0;
-
-        while !key_pressed {
-            let keys = input.get_keys();
-            for i in 0..keys.len() {
-                if keys[i] == 1 {
-                    key_pressed = true;
-                    key_value = i as u8;
-                    break;
-                }
-            }
+        if let Some(key_value) = keys.iter().position(|&key| key == 1) {
+            self.registers[register] = key_value as u8;
+            self.advance(2);
         }
-
-        self.registers[register] = key_value;
-        self.program_counter += 2;
     }
 
 
    fn set_delay_timer_register(&mut self, opcode: u16, timer: &mut Timer){
         let register = ((opcode & 0x0F00) >> 8) as usize;
         timer.set_delay_timer(self.registers[register]);
-        self.program_counter += 2;
+        self.advance(2);
     }
 
-    fn set_sound_timer_register(&mut self,opcode: u16,timer: &mut Timer, sound: &mut Sound){
+    fn set_sound_timer_register(&mut self,opcode: u16,timer: &mut Timer){
         let register = ((opcode & 0x0F00) >> 8) as usize;
         timer.set_sound_timer(self.registers[register]);
-        self.program_counter += 2;
+        self.advance(2);
     }
 
     fn add_index_register_register(&mut self, opcode: u16){
         let register = ((opcode & 0x0F00) >> 8) as usize;
-        self.index_register += self.registers[register] as u16;
-        self.program_counter += 2;
+        let sum = self.index_register as u32 + self.registers[register] as u32;
+        self.index_register = sum as u16;
+        if self.quirks.index_overflow{
+            self.registers[0xF] = (sum > 0x0FFF) as u8;
+        }
+        self.advance(2);
     }
 
     fn set_index_register_sprite(&mut self, opcode: u16){
         let register = ((opcode & 0x0F00) >> 8) as usize;
         self.index_register = self.registers[register] as u16 * 5;
-        self.program_counter += 2;
+        self.advance(2);
     }
 
     fn store_bcd(&mut self,opcode: u16, ram: &mut RAM){
         let register = ((opcode & 0x0F00) >> 8) as usize;
         let value = self.registers[register];
-        ram.write(self.index_register, value / 100);
-        ram.write(self.index_register + 1, (value / 10) % 10);
-        ram.write(self.index_register + 2, (value % 100) % 10);
-        self.program_counter += 2;
+        let digits = [value / 100, (value / 10) % 10, value % 10];
+        if !self.index_range_in_bounds(ram, digits.len()){
+            return;
+        }
+        for (i, digit) in digits.into_iter().enumerate(){
+            let address = self.index_address(ram, i);
+            ram.write(address, digit);
+        }
+        self.advance(2);
     }
 
     fn store_registers(&mut self, opcode: u16, ram: &mut RAM){
         let register = ((opcode & 0x0F00) >> 8) as usize;
+        if !self.index_range_in_bounds(ram, register + 1){
+            return;
+        }
         for i in 0..=register{
-            ram.write(self.index_register + i as u16, self.registers[i]);
+            let address = self.index_address(ram, i);
+            ram.write(address, self.registers[i]);
         }
-        self.program_counter += 2;
+        self.advance(2);
     }
 
     fn load_registers(&mut self, opcode: u16, ram: &mut RAM){
         let register = ((opcode & 0x0F00) >> 8) as usize;
+        if !self.index_range_in_bounds(ram, register + 1){
+            return;
+        }
         for i in 0..=register{
-            self.registers[i] = ram.read(self.index_register + i as u16);
+            self.registers[i] = ram.read(self.index_address(ram, i));
         }
-        self.program_counter += 2;
+        self.advance(2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::Platform;
+    use crate::SCREEN_WIDTH;
+
+    struct Machine {
+        cpu: CPU,
+        ram: RAM,
+        display: Display,
+        timer: Timer,
+        keys: [u8; 16],
+    }
+
+    impl Machine {
+        fn new() -> Machine {
+            let mut ram = RAM::new();
+            ram.load_fontset();
+            Machine {
+                cpu: CPU::new(0, Quirks::default()),
+                ram,
+                display: Display::new(),
+                timer: Timer::new(),
+                keys: [0; 16],
+            }
+        }
+
+        fn execute(&mut self, opcode: u16) {
+            self.cpu.execute_opcode(opcode, &mut self.ram, &mut self.display, &self.keys, &mut self.timer);
+        }
+    }
+
+    #[test]
+    fn clear_screen_clears_display() {
+        let mut machine = Machine::new();
+        machine.display.draw(0, 0, &[0xFF]);
+        machine.execute(0x00E0);
+        assert!(machine.display.framebuffer().iter().all(|&pixel| pixel == 0));
+        assert_eq!(machine.cpu.program_counter, 0x202);
+    }
+
+    #[test]
+    fn call_and_return_from_subroutine() {
+        let mut machine = Machine::new();
+        machine.execute(0x2400);
+        assert_eq!(machine.cpu.program_counter, 0x400);
+        assert_eq!(machine.cpu.stack_pointer, 1);
+        machine.execute(0x00EE);
+        assert_eq!(machine.cpu.program_counter, 0x202);
+        assert_eq!(machine.cpu.stack_pointer, 0);
+    }
+
+    #[test]
+    fn stack_faults_stop_the_cpu() {
+        let mut machine = Machine::new();
+        machine.ram.load_rom(&[0x00, 0xEE], 0x200).unwrap();
+        let fault = machine.cpu.cycle(&mut machine.ram, &mut machine.display, &machine.keys, &mut machine.timer);
+        assert_eq!(fault, Err(Fault::StackUnderflow { program_counter: 0x200, opcode: 0x00EE }));
+
+        machine.ram.load_rom(&[0x22, 0x00], 0x200).unwrap();
+        for _ in 0..STACK_SIZE {
+            machine.cpu.cycle(&mut machine.ram, &mut machine.display, &machine.keys, &mut machine.timer).unwrap();
+        }
+        let fault = machine.cpu.cycle(&mut machine.ram, &mut machine.display, &machine.keys, &mut machine.timer);
+        assert_eq!(fault, Err(Fault::StackOverflow { program_counter: 0x200, opcode: 0x2200 }));
+        assert_eq!(machine.cpu.stack_pointer as usize, STACK_SIZE);
+    }
+
+    #[test]
+    fn sixteen_nested_calls_return_in_order() {
+        let mut machine = Machine::new();
+        for level in 0..16 {
+            machine.execute(0x2300 + level * 2);
+        }
+        assert_eq!((machine.cpu.stack_pointer, machine.cpu.program_counter), (16, 0x31E));
+        for level in (0..16).rev() {
+            machine.execute(0x00EE);
+            assert_eq!(machine.cpu.program_counter, if level == 0 { 0x202 } else { 0x300 + level * 2 });
+        }
+        assert_eq!(machine.cpu.stack_pointer, 0);
+    }
+
+    #[test]
+    fn unknown_opcode_is_a_fault() {
+        let mut machine = Machine::new();
+        machine.ram.load_rom(&[0x80, 0x0F], 0x200).unwrap();
+        let fault = machine.cpu.cycle(&mut machine.ram, &mut machine.display, &machine.keys, &mut machine.timer);
+        assert_eq!(fault, Err(Fault::UnknownOpcode { program_counter: 0x200, opcode: 0x800F }));
+        machine.execute(0x0230);
+        assert_eq!(machine.cpu.fault.take(), Some(Fault::UnknownOpcode { program_counter: 0x200, opcode: 0x0230 }));
+        machine.execute(0x00FE);
+        assert_eq!(machine.cpu.fault.take(), Some(Fault::UnknownOpcode { program_counter: 0x200, opcode: 0x00FE }));
+        assert_eq!(machine.cpu.program_counter, 0x200);
+    }
+
+    #[test]
+    fn jump_to_address() {
+        let mut machine = Machine::new();
+        machine.execute(0x1ABC);
+        assert_eq!(machine.cpu.program_counter, 0xABC);
+    }
+
+    #[test]
+    fn jump_to_address_plus_v0() {
+        let mut machine = Machine::new();
+        machine.cpu.registers[0] = 0x10;
+        machine.execute(0xB300);
+        assert_eq!(machine.cpu.program_counter, 0x310);
+    }
+
+    #[test]
+    fn skip_if_equal_immediate() {
+        let mut machine = Machine::new();
+        machine.cpu.registers[3] = 0x42;
+        machine.execute(0x3342);
+        assert_eq!(machine.cpu.program_counter, 0x204);
+        machine.execute(0x3341);
+        assert_eq!(machine.cpu.program_counter, 0x206);
+    }
+
+    #[test]
+    fn skip_if_not_equal_immediate() {
+        let mut machine = Machine::new();
+        machine.cpu.registers[3] = 0x42;
+        machine.execute(0x4342);
+        assert_eq!(machine.cpu.program_counter, 0x202);
+        machine.execute(0x4341);
+        assert_eq!(machine.cpu.program_counter, 0x206);
+    }
+
+    #[test]
+    fn skip_if_registers_equal_and_not_equal() {
+        let mut machine = Machine::new();
+        machine.cpu.registers[1] = 7;
+        machine.cpu.registers[2] = 7;
+        machine.execute(0x5120);
+        assert_eq!(machine.cpu.program_counter, 0x204);
+        machine.execute(0x9120);
+        assert_eq!(machine.cpu.program_counter, 0x206);
+        machine.cpu.registers[2] = 8;
+        machine.execute(0x5120);
+        assert_eq!(machine.cpu.program_counter, 0x208);
+        machine.execute(0x9120);
+        assert_eq!(machine.cpu.program_counter, 0x20C);
+    }
+
+    #[test]
+    fn set_and_add_immediate() {
+        let mut machine = Machine::new();
+        machine.execute(0x6AFE);
+        assert_eq!(machine.cpu.registers[0xA], 0xFE);
+        machine.execute(0x7A03);
+        assert_eq!(machine.cpu.registers[0xA], 0x01);
+        assert_eq!(machine.cpu.registers[0xF], 0, "7XNN must not touch VF");
+    }
+
+    #[test]
+    fn register_logic_operations() {
+        let mut machine = Machine::new();
+        machine.cpu.registers[1] = 0b1100;
+        machine.cpu.registers[2] = 0b1010;
+        machine.execute(0x8122);
+        assert_eq!(machine.cpu.registers[1], 0b1000);
+        machine.cpu.registers[1] = 0b1100;
+        machine.execute(0x8121);
+        assert_eq!(machine.cpu.registers[1], 0b1110);
+        machine.cpu.registers[1] = 0b1100;
+        machine.execute(0x8123);
+        assert_eq!(machine.cpu.registers[1], 0b0110);
+        machine.execute(0x8120);
+        assert_eq!(machine.cpu.registers[1], 0b1010);
+    }
+
+    #[test]
+    fn add_registers_sets_carry() {
+        let mut machine = Machine::new();
+        machine.cpu.registers[1] = 0xF0;
+        machine.cpu.registers[2] = 0x20;
+        machine.execute(0x8124);
+        assert_eq!(machine.cpu.registers[1], 0x10);
+        assert_eq!(machine.cpu.registers[0xF], 1);
+        machine.execute(0x8124);
+        assert_eq!(machine.cpu.registers[1], 0x30);
+        assert_eq!(machine.cpu.registers[0xF], 0);
+    }
+
+    #[test]
+    fn sub_registers_sets_not_borrow() {
+        let mut machine = Machine::new();
+        machine.cpu.registers[1] = 0x10;
+        machine.cpu.registers[2] = 0x20;
+        machine.execute(0x8125);
+        assert_eq!(machine.cpu.registers[1], 0xF0);
+        assert_eq!(machine.cpu.registers[0xF], 0);
+        machine.cpu.registers[2] = 0xF0;
+        machine.execute(0x8125);
+        assert_eq!(machine.cpu.registers[1], 0x00);
+        assert_eq!(machine.cpu.registers[0xF], 1);
+    }
+
+    #[test]
+    fn reverse_sub_registers_sets_not_borrow() {
+        let mut machine = Machine::new();
+        machine.cpu.registers[1] = 0x10;
+        machine.cpu.registers[2] = 0x30;
+        machine.execute(0x8127);
+        assert_eq!(machine.cpu.registers[1], 0x20);
+        assert_eq!(machine.cpu.registers[0xF], 1);
+        machine.cpu.registers[1] = 0x31;
+        machine.execute(0x8127);
+        assert_eq!(machine.cpu.registers[1], 0xFF);
+        assert_eq!(machine.cpu.registers[0xF], 0);
+    }
+
+    #[test]
+    fn shifts_set_flag_from_shifted_out_bit() {
+        let mut machine = Machine::new();
+        machine.cpu.registers[1] = 0b1000_0011;
+        machine.execute(0x8106);
+        assert_eq!(machine.cpu.registers[1], 0b0100_0001);
+        assert_eq!(machine.cpu.registers[0xF], 1);
+        machine.execute(0x810E);
+        assert_eq!(machine.cpu.registers[1], 0b1000_0010);
+        assert_eq!(machine.cpu.registers[0xF], 0);
+        machine.execute(0x810E);
+        assert_eq!(machine.cpu.registers[1], 0b0000_0100);
+        assert_eq!(machine.cpu.registers[0xF], 1);
+    }
+
+    #[test]
+    fn alu_flag_wins_when_destination_is_vf() {
+        let cases = [
+            (0x8F14, 0xF0, 0x20, 1),
+            (0x8F14, 0x10, 0x20, 0),
+            (0x8F15, 0x30, 0x20, 1),
+            (0x8F15, 0x10, 0x20, 0),
+            (0x8F17, 0x10, 0x20, 1),
+            (0x8F17, 0x30, 0x20, 0),
+            (0x8F06, 0x03, 0x00, 1),
+            (0x8F06, 0x02, 0x00, 0),
+            (0x8F0E, 0x80, 0x00, 1),
+            (0x8F0E, 0x40, 0x00, 0),
+        ];
+        for (opcode, vf, v1, flag) in cases {
+            let mut machine = Machine::new();
+            machine.cpu.registers[0xF] = vf;
+            machine.cpu.registers[1] = v1;
+            machine.execute(opcode);
+            assert_eq!(machine.cpu.registers[0xF], flag, "{:04X} with VF={:02X} V1={:02X}", opcode, vf, v1);
+        }
+    }
+
+    #[test]
+    fn alu_reads_vf_operand_before_writing_flag() {
+        let cases = [
+            (0x81F4, 0xF0, 0x20, 0x10, 1),
+            (0x81F5, 0x30, 0x10, 0x20, 1),
+            (0x81F5, 0x10, 0x30, 0xE0, 0),
+            (0x81F7, 0x10, 0x30, 0x20, 1),
+            (0x81F7, 0x30, 0x10, 0xE0, 0),
+        ];
+        for (opcode, v1, vf, result, flag) in cases {
+            let mut machine = Machine::new();
+            machine.cpu.registers[1] = v1;
+            machine.cpu.registers[0xF] = vf;
+            machine.execute(opcode);
+            assert_eq!(machine.cpu.registers[1], result, "{:04X} with V1={:02X} VF={:02X}", opcode, v1, vf);
+            assert_eq!(machine.cpu.registers[0xF], flag, "{:04X} with V1={:02X} VF={:02X}", opcode, v1, vf);
+        }
+    }
+
+    #[test]
+    fn set_index_register() {
+        let mut machine = Machine::new();
+        machine.execute(0xA123);
+        assert_eq!(machine.cpu.index_register, 0x123);
+        assert_eq!(machine.cpu.program_counter, 0x202);
+    }
+
+    #[test]
+    fn random_is_masked_and_seeded() {
+        let mut first = Machine::new();
+        let mut second = Machine::new();
+        for _ in 0..16 {
+            first.execute(0xC50F);
+            second.execute(0xC50F);
+            assert_eq!(first.cpu.registers[5] & 0xF0, 0);
+            assert_eq!(first.cpu.registers[5], second.cpu.registers[5]);
+        }
+    }
+
+    #[test]
+    fn draw_sprite_reports_collision() {
+        let mut machine = Machine::new();
+        machine.cpu.index_register = 0x300;
+        machine.ram.write(0x300, 0b1100_0000);
+        machine.cpu.registers[0] = 2;
+        machine.cpu.registers[1] = 3;
+        machine.execute(0xD011);
+        assert_eq!(machine.display.framebuffer()[3 * SCREEN_WIDTH + 2], 1);
+        assert_eq!(machine.display.framebuffer()[3 * SCREEN_WIDTH + 3], 1);
+        assert_eq!(machine.cpu.registers[0xF], 0);
+        machine.execute(0xD011);
+        assert_eq!(machine.display.framebuffer()[3 * SCREEN_WIDTH + 2], 0);
+        assert_eq!(machine.cpu.registers[0xF], 1);
+    }
+
+    #[test]
+    fn display_wait_stalls_until_vblank() {
+        let mut machine = Machine::new();
+        machine.cpu = CPU::new(0, Quirks { display_wait: true, ..Quirks::default() });
+        machine.ram.load_rom(&[0xD0, 0x01, 0x60, 0x05], 0x200).unwrap();
+        machine.cpu.cycle(&mut machine.ram, &mut machine.display, &machine.keys, &mut machine.timer).unwrap();
+        assert!(machine.cpu.waiting_for_vblank());
+        assert_eq!(machine.cpu.cycle(&mut machine.ram, &mut machine.display, &machine.keys, &mut machine.timer).unwrap(), 0);
+        assert_eq!(machine.cpu.program_counter, 0x202);
+        machine.cpu.vblank();
+        machine.cpu.cycle(&mut machine.ram, &mut machine.display, &machine.keys, &mut machine.timer).unwrap();
+        assert_eq!(machine.cpu.registers[0], 5);
+    }
+
+    #[test]
+    fn skip_on_key_state() {
+        let mut machine = Machine::new();
+        machine.cpu.registers[4] = 0xA;
+        machine.execute(0xE49E);
+        assert_eq!(machine.cpu.program_counter, 0x202);
+        machine.execute(0xE4A1);
+        assert_eq!(machine.cpu.program_counter, 0x206);
+        machine.keys[0xA] = 1;
+        machine.execute(0xE49E);
+        assert_eq!(machine.cpu.program_counter, 0x20A);
+        machine.execute(0xE4A1);
+        assert_eq!(machine.cpu.program_counter, 0x20C);
+    }
+
+    #[test]
+    fn wait_for_key_press_blocks_until_key() {
+        let mut machine = Machine::new();
+        machine.execute(0xF30A);
+        assert_eq!(machine.cpu.program_counter, 0x200);
+        machine.keys[0x7] = 1;
+        machine.execute(0xF30A);
+        assert_eq!(machine.cpu.registers[3], 0x7);
+        assert_eq!(machine.cpu.program_counter, 0x202);
+    }
+
+    #[test]
+    fn timers_are_read_and_written() {
+        let mut machine = Machine::new();
+        machine.cpu.registers[2] = 30;
+        machine.execute(0xF215);
+        machine.execute(0xF218);
+        assert_eq!(machine.timer.read_delay_timer(), 30);
+        assert_eq!(machine.timer.read_sound_timer(), 30);
+        machine.timer.tick();
+        machine.execute(0xF507);
+        assert_eq!(machine.cpu.registers[5], 29);
+    }
+
+    #[test]
+    fn add_to_index_register() {
+        let mut machine = Machine::new();
+        machine.cpu.index_register = 0x100;
+        machine.cpu.registers[6] = 0x22;
+        machine.execute(0xF61E);
+        assert_eq!(machine.cpu.index_register, 0x122);
+    }
+
+    #[test]
+    fn index_register_points_at_font_glyph() {
+        let mut machine = Machine::new();
+        machine.cpu.registers[1] = 0xA;
+        machine.execute(0xF129);
+        assert_eq!(machine.cpu.index_register, 50);
+        assert_eq!(machine.ram.read(machine.cpu.index_register), 0xF0);
+    }
+
+    #[test]
+    fn store_bcd_writes_three_digits() {
+        let mut machine = Machine::new();
+        machine.cpu.index_register = 0x300;
+        machine.cpu.registers[0] = 249;
+        machine.execute(0xF033);
+        assert_eq!(machine.ram.read(0x300), 2);
+        assert_eq!(machine.ram.read(0x301), 4);
+        assert_eq!(machine.ram.read(0x302), 9);
+        machine.cpu.registers[0] = 7;
+        machine.execute(0xF033);
+        assert_eq!(machine.ram.read(0x300), 0);
+        assert_eq!(machine.ram.read(0x301), 0);
+        assert_eq!(machine.ram.read(0x302), 7);
+    }
+
+    #[test]
+    fn store_and_load_registers() {
+        let mut machine = Machine::new();
+        machine.cpu.index_register = 0x300;
+        for i in 0..4 {
+            machine.cpu.registers[i] = i as u8 + 1;
+        }
+        machine.execute(0xF255);
+        assert_eq!(machine.ram.read(0x300), 1);
+        assert_eq!(machine.ram.read(0x302), 3);
+        assert_eq!(machine.ram.read(0x303), 0, "FX55 stores V0 through VX only");
+
+        machine.cpu.registers = [0; REGISTER_COUNT];
+        machine.execute(0xF165);
+        assert_eq!(machine.cpu.registers[0], 1);
+        assert_eq!(machine.cpu.registers[1], 2);
+        assert_eq!(machine.cpu.registers[2], 0, "FX65 loads V0 through VX only");
+    }
+
+    #[test]
+    fn index_accesses_wrap_at_end_of_memory() {
+        let mut machine = Machine::new();
+        machine.cpu.index_register = 0xFFE;
+        machine.cpu.registers[0] = 0x11;
+        machine.cpu.registers[1] = 0x22;
+        machine.cpu.registers[2] = 0x33;
+        machine.execute(0xF255);
+        assert_eq!(machine.ram.read(0xFFE), 0x11);
+        assert_eq!(machine.ram.read(0xFFF), 0x22);
+        assert_eq!(machine.ram.read(0x000), 0x33);
+    }
+
+    #[test]
+    fn xo_chip_memory_does_not_wrap_at_4k() {
+        let mut machine = Machine::new();
+        machine.ram = RAM::with_size(Platform::XoChip.memory_size());
+        machine.cpu.index_register = 0xFFF;
+        machine.cpu.registers[0] = 0x11;
+        machine.cpu.registers[1] = 0x22;
+        machine.execute(0xF155);
+        assert_eq!(machine.ram.read(0x1000), 0x22);
+        assert_eq!(machine.ram.read(0x000), 0x00);
+    }
+
+    #[test]
+    fn index_overflow_quirk_sets_vf() {
+        let mut machine = Machine::new();
+        machine.cpu.index_register = 0xFFF;
+        machine.cpu.registers[1] = 1;
+        machine.execute(0xF11E);
+        assert_eq!(machine.cpu.index_register, 0x1000);
+        assert_eq!(machine.cpu.registers[0xF], 0);
+
+        machine.cpu = CPU::new(0, Quirks { index_overflow: true, ..Quirks::default() });
+        machine.cpu.index_register = 0xFFF;
+        machine.cpu.registers[1] = 1;
+        machine.execute(0xF11E);
+        assert_eq!(machine.cpu.registers[0xF], 1);
+        machine.execute(0xF01E);
+        assert_eq!(machine.cpu.registers[0xF], 1);
+        machine.cpu.index_register = 0x100;
+        machine.execute(0xF11E);
+        assert_eq!(machine.cpu.registers[0xF], 0);
+    }
+
+    #[test]
+    fn strict_memory_reports_out_of_bounds_access() {
+        let mut machine = Machine::new();
+        machine.cpu = CPU::new(0, Quirks { strict_memory: true, ..Quirks::default() });
+        machine.ram.load_rom(&[0xAF, 0xFE, 0xF2, 0x65], 0x200).unwrap();
+        machine.cpu.cycle(&mut machine.ram, &mut machine.display, &machine.keys, &mut machine.timer).unwrap();
+        let fault = machine.cpu.cycle(&mut machine.ram, &mut machine.display, &machine.keys, &mut machine.timer);
+        assert_eq!(fault, Err(Fault::OutOfBounds { program_counter: 0x202, opcode: 0xF265, address: 0x1000 }));
+        assert_eq!(machine.cpu.program_counter, 0x202);
+    }
+
+    #[test]
+    fn strict_memory_reports_program_counter_past_end() {
+        let mut machine = Machine::new();
+        machine.cpu = CPU::new(0, Quirks { strict_memory: true, ..Quirks::default() });
+        machine.cpu.program_counter = 0xFFF;
+        let fault = machine.cpu.cycle(&mut machine.ram, &mut machine.display, &machine.keys, &mut machine.timer);
+        assert!(matches!(fault, Err(Fault::OutOfBounds { program_counter: 0xFFF, address: 0x1000, .. })));
+    }
+
+    #[test]
+    fn strict_memory_reports_program_counter_running_off_the_end() {
+        let mut machine = Machine::new();
+        machine.cpu = CPU::new(0, Quirks { strict_memory: true, ..Quirks::default() });
+        machine.cpu.program_counter = 0xFFE;
+        machine.ram.write(0xFFE, 0x60);
+        machine.ram.write(0xFFF, 0x05);
+        let fault = machine.cpu.cycle(&mut machine.ram, &mut machine.display, &machine.keys, &mut machine.timer);
+        assert_eq!(fault, Err(Fault::OutOfBounds { program_counter: 0xFFE, opcode: 0x6005, address: 0x1000 }));
+        assert_eq!(machine.cpu.program_counter, 0xFFE);
+
+        machine.ram = RAM::with_size(Platform::XoChip.memory_size());
+        machine.cpu.program_counter = 0xFFFE;
+        machine.ram.write(0xFFFE, 0x60);
+        let fault = machine.cpu.cycle(&mut machine.ram, &mut machine.display, &machine.keys, &mut machine.timer);
+        assert_eq!(fault, Err(Fault::OutOfBounds { program_counter: 0xFFFE, opcode: 0x6000, address: 0x10000 }));
+    }
+
+    #[test]
+    fn strict_memory_reports_jumps_past_the_end() {
+        let mut machine = Machine::new();
+        machine.cpu = CPU::new(0, Quirks { strict_memory: true, ..Quirks::default() });
+        machine.cpu.registers[0] = 0xFF;
+        machine.ram.load_rom(&[0xBF, 0xFF], 0x200).unwrap();
+        let fault = machine.cpu.cycle(&mut machine.ram, &mut machine.display, &machine.keys, &mut machine.timer);
+        assert_eq!(fault, Err(Fault::OutOfBounds { program_counter: 0x200, opcode: 0xBFFF, address: 0x10FE }));
+        assert_eq!(machine.cpu.program_counter, 0x200);
+
+        machine.cpu = CPU::new(0, Quirks::default());
+        machine.cpu.registers[0] = 0xFF;
+        machine.cpu.cycle(&mut machine.ram, &mut machine.display, &machine.keys, &mut machine.timer).unwrap();
+        assert_eq!(machine.cpu.program_counter, 0x0FE);
+    }
+
+    #[test]
+    fn strict_memory_faults_before_writing() {
+        let mut machine = Machine::new();
+        machine.cpu = CPU::new(0, Quirks { strict_memory: true, ..Quirks::default() });
+        machine.cpu.index_register = 0xFFE;
+        machine.cpu.registers[0] = 123;
+        machine.ram.load_rom(&[0xF0, 0x33], 0x200).unwrap();
+        let fault = machine.cpu.cycle(&mut machine.ram, &mut machine.display, &machine.keys, &mut machine.timer);
+        assert!(matches!(fault, Err(Fault::OutOfBounds { address: 0x1000, .. })));
+        assert_eq!((machine.ram.read(0xFFE), machine.ram.read(0xFFF), machine.ram.read(0)), (0, 0, 0xF0));
+    }
+
+    #[test]
+    fn program_counter_wraps_at_end_of_memory() {
+        let mut machine = Machine::new();
+        machine.cpu.program_counter = 0xFFE;
+        machine.ram.write(0xFFE, 0x60);
+        machine.ram.write(0xFFF, 0x05);
+        machine.cpu.cycle(&mut machine.ram, &mut machine.display, &machine.keys, &mut machine.timer).unwrap();
+        assert_eq!(machine.cpu.program_counter, 0);
+
+        machine.ram = RAM::with_size(Platform::XoChip.memory_size());
+        machine.cpu.program_counter = 0xFFFE;
+        machine.ram.write(0xFFFE, 0x30);
+        machine.cpu.registers[0] = 0;
+        machine.cpu.cycle(&mut machine.ram, &mut machine.display, &machine.keys, &mut machine.timer).unwrap();
+        assert_eq!(machine.cpu.program_counter, 2);
+    }
+
+    #[test]
+    fn sprites_clip_at_screen_edges() {
+        let mut machine = Machine::new();
+        machine.cpu.index_register = 0x300;
+        for row in 0..4 {
+            machine.ram.write(0x300 + row, 0xFF);
+        }
+        machine.cpu.registers[0] = 60;
+        machine.cpu.registers[1] = 30;
+        machine.execute(0xD014);
+        let lit: Vec<usize> = (0..machine.display.framebuffer().len()).filter(|&index| machine.display.framebuffer()[index] == 1).collect();
+        assert_eq!(lit, vec![30 * SCREEN_WIDTH + 60, 30 * SCREEN_WIDTH + 61, 30 * SCREEN_WIDTH + 62, 30 * SCREEN_WIDTH + 63,
+            31 * SCREEN_WIDTH + 60, 31 * SCREEN_WIDTH + 61, 31 * SCREEN_WIDTH + 62, 31 * SCREEN_WIDTH + 63]);
+
+        machine.display.clear();
+        machine.cpu.registers[0] = 66;
+        machine.cpu.registers[1] = 33;
+        machine.execute(0xD011);
+        assert_eq!(machine.display.framebuffer()[SCREEN_WIDTH + 2], 1);
+    }
+
+    #[test]
+    fn key_skips_use_the_low_nibble() {
+        let mut machine = Machine::new();
+        machine.cpu.registers[0] = 0x1F;
+        machine.keys[0xF] = 1;
+        machine.execute(0xE09E);
+        assert_eq!(machine.cpu.program_counter, 0x204);
+        machine.execute(0xE0A1);
+        assert_eq!(machine.cpu.program_counter, 0x206);
     }
 }
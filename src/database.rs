@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::palette::{parse_color, Palette};
+use crate::platform::Platform;
+
+const BUNDLED_HASHES: &str = include_str!("../database/sha1-hashes.json");
+const BUNDLED_PROGRAMS: &str = include_str!("../database/programs.json");
+
+pub struct RomInfo{
+    pub title: String,
+    pub authors: Vec<String>,
+    pub platform: Option<Platform>,
+    pub display_wait: Option<bool>,
+    pub tickrate: Option<u32>,
+    pub colors: Vec<[u8; 3]>,
+    pub keys: Vec<(String, u8)>,
+}
+
+impl RomInfo{
+    pub fn palette(&self) -> Option<Palette>{
+        if self.colors.is_empty(){
+            return None;
+        }
+        let mut palette = Palette::new();
+        for (pixel, color) in self.colors.iter().take(4).enumerate(){
+            palette.set_color(pixel as u8, *color);
+        }
+        Some(palette)
+    }
+}
+
+pub struct Database{
+    hashes: HashMap<String, usize>,
+    programs: Vec<Value>,
+}
+
+impl Database{
+    pub fn bundled() -> Database{
+        Database::parse(BUNDLED_HASHES, BUNDLED_PROGRAMS).unwrap()
+    }
+
+    pub fn parse(hashes: &str, programs: &str) -> Result<Database, String>{
+        let hashes: HashMap<String, usize> = serde_json::from_str(hashes).map_err(|e| format!("sha1-hashes.json: {}", e))?;
+        let programs: Vec<Value> = serde_json::from_str(programs).map_err(|e| format!("programs.json: {}", e))?;
+        Ok(Database{
+            hashes,
+            programs,
+        })
+    }
+
+    pub fn lookup(&self, sha1: &str) -> Option<RomInfo>{
+        let program = self.programs.get(*self.hashes.get(sha1)?)?;
+        let rom = &program["roms"][sha1];
+
+        let (platform, display_wait) = match rom["platforms"].get(0).and_then(Value::as_str){
+            Some(name) => {
+                let (platform, display_wait) = platform_named(name);
+                (platform, rom["quirkyPlatforms"][name]["vblank"].as_bool().or(display_wait))
+            },
+            None => (None, None),
+        };
+
+        let colors = rom["colors"]["pixels"].as_array()
+            .map(|pixels| pixels.iter().filter_map(|color| parse_color(color.as_str()?).ok()).collect())
+            .unwrap_or_default();
+        let keys = rom["keys"].as_object()
+            .map(|keys| keys.iter().filter_map(|(name, key)| Some((name.clone(), key.as_u64()? as u8))).collect())
+            .unwrap_or_default();
+
+        Some(RomInfo{
+            title: program["title"].as_str().unwrap_or("").to_string(),
+            authors: program["authors"].as_array()
+                .map(|authors| authors.iter().filter_map(|author| Some(author.as_str()?.to_string())).collect())
+                .unwrap_or_default(),
+            platform,
+            display_wait,
+            tickrate: rom["tickrate"].as_u64().map(|tickrate| tickrate as u32),
+            colors,
+            keys,
+        })
+    }
+}
+
+fn platform_named(name: &str) -> (Option<Platform>, Option<bool>){
+    match name{
+        "originalChip8" | "hybridVIP" => (Some(Platform::Chip8), Some(true)),
+        "modernChip8" => (Some(Platform::Chip8), Some(false)),
+        "chip8x" => (Some(Platform::Chip8X), Some(true)),
+        "chip48" | "superchip1" | "superchip" => (Some(Platform::SuperChip), Some(false)),
+        "xochip" => (Some(Platform::XoChip), Some(false)),
+        _ => (None, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_database_finds_ibm_logo() {
+        let info = Database::bundled().lookup("1ba58656810b67fd131eb9af3e3987863bf26c90").unwrap();
+        assert_eq!(info.title, "IBM Logo");
+        assert_eq!(info.platform, Some(Platform::Chip8));
+        assert!(Database::bundled().lookup("0000000000000000000000000000000000000000").is_none());
+    }
+
+    #[test]
+    fn lookup_reads_settings() {
+        let hashes = r#"{"abc": 0}"#;
+        let programs = r##"[{
+            "title": "Game",
+            "authors": ["Someone"],
+            "roms": {"abc": {
+                "platforms": ["originalChip8"],
+                "quirkyPlatforms": {"originalChip8": {"vblank": false}},
+                "tickrate": 30,
+                "colors": {"pixels": ["#000000", "#ff8000"]},
+                "keys": {"up": 5}
+            }}
+        }]"##;
+        let info = Database::parse(hashes, programs).unwrap().lookup("abc").unwrap();
+        assert_eq!(info.authors, vec!["Someone".to_string()]);
+        assert_eq!(info.display_wait, Some(false));
+        assert_eq!(info.tickrate, Some(30));
+        assert_eq!(info.colors, vec![[0, 0, 0], [0xff, 0x80, 0]]);
+        assert_eq!(info.keys, vec![("up".to_string(), 5)]);
+    }
+
+    #[test]
+    fn quirks_come_from_the_chosen_platform() {
+        let hashes = r#"{"abc": 0}"#;
+        let programs = r#"[{
+            "title": "Game",
+            "roms": {"abc": {
+                "platforms": ["modernChip8", "originalChip8"],
+                "quirkyPlatforms": {"originalChip8": {"vblank": true}, "modernChip8": {"shift": true}}
+            }}
+        }]"#;
+        let info = Database::parse(hashes, programs).unwrap().lookup("abc").unwrap();
+        assert_eq!(info.display_wait, Some(false));
+    }
+}
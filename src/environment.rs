@@ -0,0 +1,324 @@
+use serde_json::Value;
+
+use crate::fault::Fault;
+use crate::machine::Machine;
+use crate::movie::keys_from_mask;
+use crate::platform::Platform;
+use crate::quirks::Quirks;
+use crate::timing::Timing;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Source{
+    Memory(u16),
+    Register(u8),
+    Bcd(u16),
+    BcdAtIndex,
+}
+
+impl Source{
+    fn parse(rule: &Value) -> Result<Source, String>{
+        if let Some(address) = rule.get("memory"){
+            Ok(Source::Memory(parse_address(address)?))
+        }else if let Some(register) = rule.get("register"){
+            let number = match register.as_str().and_then(|name| name.strip_prefix(['V', 'v'])){
+                Some(digit) => u32::from_str_radix(digit, 16).ok(),
+                None => parse_number(register).ok(),
+            };
+            match number{
+                Some(number) if number <= 0xF => Ok(Source::Register(number as u8)),
+                _ => Err(format!("invalid register {}", register)),
+            }
+        }else if let Some(address) = rule.get("bcd"){
+            match address.as_str(){
+                Some("I" | "i") => Ok(Source::BcdAtIndex),
+                _ => Ok(Source::Bcd(parse_address(address)?)),
+            }
+        }else{
+            Err(format!("rule {} reads no memory, register or bcd", rule))
+        }
+    }
+
+    pub fn read(self, machine: &Machine) -> u32{
+        match self{
+            Source::Memory(address) => machine.ram().read(address) as u32,
+            Source::Register(register) => machine.cpu().registers()[register as usize] as u32,
+            Source::Bcd(address) => bcd(machine, address),
+            Source::BcdAtIndex => bcd(machine, machine.cpu().index_register()),
+        }
+    }
+}
+
+fn bcd(machine: &Machine, address: u16) -> u32{
+    (0..3).fold(0, |value, digit| value * 10 + machine.ram().read(address.wrapping_add(digit)) as u32)
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Comparison{
+    Equals,
+    Below,
+    Above,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Reward{
+    pub source: Source,
+    pub scale: f64,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Condition{
+    pub source: Source,
+    pub comparison: Comparison,
+    pub value: u32,
+}
+
+impl Condition{
+    pub fn holds(&self, machine: &Machine) -> bool{
+        let value = self.source.read(machine);
+        match self.comparison{
+            Comparison::Equals => value == self.value,
+            Comparison::Below => value < self.value,
+            Comparison::Above => value > self.value,
+        }
+    }
+}
+
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Rules{
+    pub reward: Vec<Reward>,
+    pub done: Vec<Condition>,
+    pub max_frames: Option<u32>,
+}
+
+impl Rules{
+    pub fn parse(json: &str) -> Result<Rules, String>{
+        let rules: Value = serde_json::from_str(json).map_err(|e| format!("rules: {}", e))?;
+        let list = |name: &str| rules[name].as_array().cloned().unwrap_or_default();
+
+        let mut reward = Vec::new();
+        for rule in list("reward"){
+            reward.push(Reward{
+                source: Source::parse(&rule)?,
+                scale: rule.get("scale").map(|scale| scale.as_f64().ok_or(format!("invalid scale {}", scale))).transpose()?.unwrap_or(1.0),
+            });
+        }
+
+        let mut done = Vec::new();
+        for rule in list("done"){
+            let (comparison, value) = match (rule.get("equals"), rule.get("below"), rule.get("above")){
+                (Some(value), None, None) => (Comparison::Equals, value),
+                (None, Some(value), None) => (Comparison::Below, value),
+                (None, None, Some(value)) => (Comparison::Above, value),
+                _ => return Err(format!("rule {} needs one of equals, below or above", rule)),
+            };
+            done.push(Condition{
+                source: Source::parse(&rule)?,
+                comparison,
+                value: parse_number(value)?,
+            });
+        }
+
+        let max_frames = match &rules["max_frames"]{
+            Value::Null => None,
+            frames => Some(parse_number(frames)?),
+        };
+        Ok(Rules{
+            reward,
+            done,
+            max_frames,
+        })
+    }
+}
+
+fn parse_number(value: &Value) -> Result<u32, String>{
+    let number = match value{
+        Value::Number(number) => number.as_u64(),
+        Value::String(text) => match text.strip_prefix("0x"){
+            Some(hex) => u64::from_str_radix(hex, 16).ok(),
+            None => text.parse().ok(),
+        },
+        _ => None,
+    };
+    number.and_then(|number| u32::try_from(number).ok()).ok_or(format!("invalid number {}", value))
+}
+
+fn parse_address(value: &Value) -> Result<u16, String>{
+    u16::try_from(parse_number(value)?).map_err(|_| format!("invalid address {}", value))
+}
+
+pub struct Step<'a>{
+    pub observation: &'a [u8],
+    pub reward: f64,
+    pub done: bool,
+}
+
+pub struct Environment{
+    rom: Vec<u8>,
+    seed: u64,
+    platform: Platform,
+    quirks: Quirks,
+    timing: Timing,
+    rules: Rules,
+    frames_per_step: u32,
+    machine: Machine,
+    frame: u32,
+    scores: Vec<u32>,
+    fault: Option<Fault>,
+}
+
+impl Environment{
+    pub fn new(rom: &[u8], seed: u64, platform: Platform, quirks: Quirks, timing: Timing, rules: Rules, frames_per_step: u32) -> Result<Environment, String>{
+        let machine = Machine::new(rom, seed, platform, quirks, timing)?;
+        let mut environment = Environment{
+            rom: rom.to_vec(),
+            seed,
+            platform,
+            quirks,
+            timing,
+            rules,
+            frames_per_step: frames_per_step.max(1),
+            machine,
+            frame: 0,
+            scores: Vec::new(),
+            fault: None,
+        };
+        environment.reset();
+        Ok(environment)
+    }
+
+    pub fn set_seed(&mut self, seed: u64){
+        self.seed = seed;
+    }
+
+    pub fn reset(&mut self) -> &[u8]{
+        self.machine = Machine::new(&self.rom, self.seed, self.platform, self.quirks, self.timing).unwrap();
+        self.frame = 0;
+        self.scores = self.rules.reward.iter().map(|reward| reward.source.read(&self.machine)).collect();
+        self.fault = None;
+        self.machine.display().framebuffer()
+    }
+
+    pub fn step(&mut self, action: u16) -> Step<'_>{
+        if !self.done(){
+            let keys = keys_from_mask(action);
+            for _ in 0..self.frames_per_step{
+                if let Err(fault) = self.machine.run_frame(&keys){
+                    self.fault = Some(fault);
+                    break;
+                }
+                self.frame += 1;
+                if self.done(){
+                    break;
+                }
+            }
+        }
+
+        let mut reward = 0.0;
+        for (rule, score) in self.rules.reward.iter().zip(&mut self.scores){
+            let value = rule.source.read(&self.machine);
+            reward += (value as f64 - *score as f64) * rule.scale;
+            *score = value;
+        }
+        Step{
+            observation: self.machine.display().framebuffer(),
+            reward,
+            done: self.done(),
+        }
+    }
+
+    pub fn done(&self) -> bool{
+        self.fault.is_some()
+            || self.rules.max_frames.is_some_and(|frames| self.frame >= frames)
+            || self.rules.done.iter().any(|condition| condition.holds(&self.machine))
+    }
+
+    pub fn frame(&self) -> u32{
+        self.frame
+    }
+
+    pub fn fault(&self) -> Option<&Fault>{
+        self.fault.as_ref()
+    }
+
+    pub fn machine(&self) -> &Machine{
+        &self.machine
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COUNTER: [u8; 8] = [0x71, 0x01, 0xA3, 0x00, 0xF1, 0x33, 0x12, 0x00];
+    const RANDOM: [u8; 8] = [0xC0, 0xFF, 0xF0, 0x29, 0xD1, 0x25, 0x12, 0x00];
+
+    fn environment(rom: &[u8], rules: &str, seed: u64) -> Environment {
+        Environment::new(rom, seed, Platform::Chip8, Quirks::default(), Timing::Fixed(4), Rules::parse(rules).unwrap(), 2).unwrap()
+    }
+
+    #[test]
+    fn parses_rules() {
+        let rules = Rules::parse(r#"{
+            "reward": [{"bcd": "0x300"}, {"register": "VD", "scale": -10}],
+            "done": [{"memory": 768, "above": "5"}],
+            "max_frames": 100
+        }"#).unwrap();
+        assert_eq!(rules.reward, vec![
+            Reward { source: Source::Bcd(0x300), scale: 1.0 },
+            Reward { source: Source::Register(0xD), scale: -10.0 },
+        ]);
+        assert_eq!(rules.done, vec![Condition { source: Source::Memory(0x300), comparison: Comparison::Above, value: 5 }]);
+        assert_eq!(rules.max_frames, Some(100));
+
+        assert!(Rules::parse(r#"{"reward": [{"register": "VG"}]}"#).is_err());
+        assert!(Rules::parse(r#"{"done": [{"memory": 0}]}"#).is_err());
+        assert!(Rules::parse(r#"{"reward": [{"register": 261}]}"#).is_err());
+        assert!(Rules::parse(r#"{"reward": [{"memory": "0x10300"}]}"#).is_err());
+        assert!(Rules::parse(r#"{"reward": [{"bcd": 65536}]}"#).is_err());
+        assert!(Rules::parse(r#"{"max_frames": 4294967296}"#).is_err());
+        assert!(Rules::parse(r#"{"max_frames": "many"}"#).is_err());
+    }
+
+    #[test]
+    fn bcd_can_follow_the_index_register() {
+        let rules = Rules::parse(r#"{"reward": [{"bcd": "I"}]}"#).unwrap();
+        assert_eq!(rules.reward[0].source, Source::BcdAtIndex);
+        let mut environment = environment(&COUNTER, r#"{"reward": [{"bcd": "I"}]}"#, 0);
+        environment.step(0);
+        for _ in 0..3 {
+            assert_eq!(environment.step(0).reward, 2.0);
+        }
+        assert_eq!(environment.machine().cpu().index_register(), 0x300);
+    }
+
+    #[test]
+    fn rewards_score_until_done() {
+        let mut environment = environment(&COUNTER, r#"{"reward": [{"bcd": "0x300"}], "done": [{"register": "V1", "equals": 10}]}"#, 0);
+        for _ in 0..4 {
+            let step = environment.step(0);
+            assert_eq!((step.reward, step.done), (2.0, false));
+        }
+        let step = environment.step(0);
+        assert_eq!((step.reward, step.done), (2.0, true));
+        assert_eq!(environment.step(0).reward, 0.0);
+
+        environment.reset();
+        assert_eq!(environment.frame(), 0);
+        assert!(!environment.step(0).done);
+    }
+
+    #[test]
+    fn same_seed_replays_the_same_episode() {
+        let mut first = environment(&RANDOM, r#"{"max_frames": 6}"#, 3);
+        let mut second = environment(&RANDOM, r#"{"max_frames": 6}"#, 3);
+        for _ in 0..3 {
+            assert_eq!(first.step(0).observation, second.step(0).observation);
+        }
+        assert!(first.done());
+
+        let observation = first.machine().display().framebuffer().to_vec();
+        first.reset();
+        while !first.step(0).done {}
+        assert_eq!(first.machine().display().framebuffer(), observation);
+    }
+}
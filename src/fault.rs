@@ -0,0 +1,28 @@
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Fault{
+    OutOfBounds { program_counter: u16, opcode: u16, address: u32 },
+    UnknownOpcode { program_counter: u16, opcode: u16 },
+    StackOverflow { program_counter: u16, opcode: u16 },
+    StackUnderflow { program_counter: u16, opcode: u16 },
+}
+
+impl fmt::Display for Fault{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        match self{
+            Fault::OutOfBounds { program_counter, opcode, address } => {
+                write!(f, "out of bounds access to {:#06x} by {:04X} at {:#05x}", address, opcode, program_counter)
+            },
+            Fault::UnknownOpcode { program_counter, opcode } => {
+                write!(f, "unknown opcode {:04X} at {:#05x}", opcode, program_counter)
+            },
+            Fault::StackOverflow { program_counter, opcode } => {
+                write!(f, "stack overflow by {:04X} at {:#05x}", opcode, program_counter)
+            },
+            Fault::StackUnderflow { program_counter, opcode } => {
+                write!(f, "stack underflow by {:04X} at {:#05x}", opcode, program_counter)
+            },
+        }
+    }
+}
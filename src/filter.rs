@@ -0,0 +1,124 @@
+use crate::palette::Palette;
+use crate::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+const SCANLINE_BRIGHTNESS: u16 = 128;
+
+pub struct Filter{
+    persistence: u8,
+    scanlines: bool,
+    fade: [u8; SCREEN_WIDTH * SCREEN_HEIGHT],
+    colors: [[u8; 3]; SCREEN_WIDTH * SCREEN_HEIGHT],
+    image: Vec<u8>,
+}
+
+impl Filter{
+    pub fn new(persistence: u8, scanlines: bool) -> Filter{
+        let rows = if scanlines { SCREEN_HEIGHT * 2 } else { SCREEN_HEIGHT };
+        Filter{
+            persistence,
+            scanlines,
+            fade: [0; SCREEN_WIDTH * SCREEN_HEIGHT],
+            colors: [[0; 3]; SCREEN_WIDTH * SCREEN_HEIGHT],
+            image: vec![0; SCREEN_WIDTH * rows * 3],
+        }
+    }
+
+    pub fn width(&self) -> usize{
+        SCREEN_WIDTH
+    }
+
+    pub fn height(&self) -> usize{
+        self.image.len() / (SCREEN_WIDTH * 3)
+    }
+
+    pub fn apply(&mut self, framebuffer: &[u8], palette: &Palette) -> &[u8]{
+        let background = palette.color(0);
+        let rows_per_line = if self.scanlines { 2 } else { 1 };
+        let pitch = SCREEN_WIDTH * 3;
+
+        for (index, &pixel) in framebuffer.iter().enumerate(){
+            let color = if pixel != 0{
+                self.fade[index] = self.persistence;
+                self.colors[index] = palette.color(pixel);
+                self.colors[index]
+            }else if self.fade[index] > 0{
+                let color = blend(background, self.colors[index], self.fade[index], self.persistence as i32 + 1);
+                self.fade[index] -= 1;
+                color
+            }else{
+                background
+            };
+
+            let offset = (index / SCREEN_WIDTH) * rows_per_line * pitch + (index % SCREEN_WIDTH) * 3;
+            self.image[offset..offset + 3].copy_from_slice(&color);
+            if self.scanlines{
+                let dimmed = color.map(|channel| (channel as u16 * SCANLINE_BRIGHTNESS / 255) as u8);
+                self.image[offset + pitch..offset + pitch + 3].copy_from_slice(&dimmed);
+            }
+        }
+        &self.image
+    }
+}
+
+fn blend(from: [u8; 3], to: [u8; 3], amount: u8, total: i32) -> [u8; 3]{
+    let mut color = [0; 3];
+    for i in 0..3{
+        let from = from[i] as i32;
+        let to = to[i] as i32;
+        color[i] = (from + (to - from) * amount as i32 / total) as u8;
+    }
+    color
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(image: &[u8], index: usize) -> [u8; 3] {
+        [image[index * 3], image[index * 3 + 1], image[index * 3 + 2]]
+    }
+
+    #[test]
+    fn persistence_fades_pixels_out_over_n_frames() {
+        let palette = Palette::new();
+        let (background, foreground) = (palette.color(0), palette.color(1));
+        let mut lit = [0; SCREEN_WIDTH * SCREEN_HEIGHT];
+        lit[0] = 1;
+        let dark = [0; SCREEN_WIDTH * SCREEN_HEIGHT];
+
+        let mut filter = Filter::new(1, false);
+        assert_eq!(pixel(filter.apply(&lit, &palette), 0), foreground);
+        assert_eq!(pixel(filter.apply(&dark, &palette), 0), blend(background, foreground, 1, 2));
+        assert_ne!(blend(background, foreground, 1, 2), background);
+        assert_eq!(pixel(filter.apply(&dark, &palette), 0), background);
+
+        let mut filter = Filter::new(3, false);
+        filter.apply(&lit, &palette);
+        for amount in [3, 2, 1] {
+            assert_eq!(pixel(filter.apply(&dark, &palette), 0), blend(background, foreground, amount, 4));
+        }
+        assert_eq!(pixel(filter.apply(&dark, &palette), 0), background);
+
+        let mut filter = Filter::new(0, false);
+        filter.apply(&lit, &palette);
+        assert_eq!(pixel(filter.apply(&dark, &palette), 0), background);
+    }
+
+    #[test]
+    fn scanlines_double_the_rows_and_dim_every_other_one() {
+        let palette = Palette::new();
+        let mut framebuffer = [0; SCREEN_WIDTH * SCREEN_HEIGHT];
+        framebuffer[SCREEN_WIDTH] = 1;
+
+        let mut filter = Filter::new(0, true);
+        assert_eq!(filter.height(), SCREEN_HEIGHT * 2);
+        let image = filter.apply(&framebuffer, &palette);
+        let dim = |color: [u8; 3]| color.map(|channel| (channel as u16 * SCANLINE_BRIGHTNESS / 255) as u8);
+        assert_eq!(pixel(image, 0), palette.color(0));
+        assert_eq!(pixel(image, SCREEN_WIDTH), dim(palette.color(0)));
+        assert_eq!(pixel(image, SCREEN_WIDTH * 2), palette.color(1));
+        assert_eq!(pixel(image, SCREEN_WIDTH * 3), dim(palette.color(1)));
+
+        assert_eq!(Filter::new(0, false).height(), SCREEN_HEIGHT);
+    }
+}
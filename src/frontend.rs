@@ -0,0 +1,35 @@
+use std::path::PathBuf;
+
+use crate::display::Display;
+use crate::palette::Palette;
+
+pub use crate::audio::AudioSink;
+
+pub enum Command{
+    Quit,
+    NextPalette,
+    ToggleFullscreen,
+    Up,
+    Down,
+    Select,
+    Back,
+    Load(PathBuf),
+}
+
+pub trait Renderer{
+    fn render(&mut self, display: &Display, palette: &Palette);
+
+    fn set_title(&mut self, _title: &str){}
+
+    fn toggle_fullscreen(&mut self){}
+}
+
+pub trait InputSource{
+    fn poll(&mut self) -> Vec<Command>;
+
+    fn keys(&self) -> [u8; 16];
+
+    fn bind(&mut self, _name: &str, _key: u8){}
+
+    fn reset_bindings(&mut self){}
+}
@@ -0,0 +1,148 @@
+use std::fmt::Write;
+
+const KITTY_CHUNK: usize = 4096;
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Protocol{
+    Sixel,
+    Kitty,
+}
+
+impl Protocol{
+    pub fn named(name: &str) -> Option<Protocol>{
+        match name{
+            "sixel" => Some(Protocol::Sixel),
+            "kitty" => Some(Protocol::Kitty),
+            _ => None,
+        }
+    }
+}
+
+pub fn encode(protocol: Protocol, image: &[u8], width: usize, height: usize, scale: usize) -> String{
+    let scaled = scale_image(image, width, height, scale);
+    match protocol{
+        Protocol::Sixel => sixel(&scaled, width * scale, height * scale),
+        Protocol::Kitty => kitty(&scaled, width * scale, height * scale),
+    }
+}
+
+fn scale_image(image: &[u8], width: usize, height: usize, scale: usize) -> Vec<u8>{
+    let mut scaled = Vec::with_capacity(image.len() * scale * scale);
+    for y in 0..height * scale{
+        for x in 0..width * scale{
+            let index = ((y / scale) * width + x / scale) * 3;
+            scaled.extend_from_slice(&image[index..index + 3]);
+        }
+    }
+    scaled
+}
+
+fn sixel(image: &[u8], width: usize, height: usize) -> String{
+    let pixels: Vec<[u8; 3]> = image.chunks(3).map(|pixel| [pixel[0], pixel[1], pixel[2]]).collect();
+    let mut colors: Vec<[u8; 3]> = Vec::new();
+    let indices: Vec<usize> = pixels.iter().map(|pixel| {
+        match colors.iter().position(|color| color == pixel){
+            Some(index) => index,
+            None => {
+                colors.push(*pixel);
+                colors.len() - 1
+            },
+        }
+    }).collect();
+
+    let mut out = format!("\x1bPq\"1;1;{};{}", width, height);
+    for (index, [r, g, b]) in colors.iter().enumerate(){
+        let percent = |channel: &u8| *channel as u32 * 100 / 255;
+        write!(out, "#{};2;{};{};{}", index, percent(r), percent(g), percent(b)).unwrap();
+    }
+    for band in (0..height).step_by(6){
+        for color in 0..colors.len(){
+            let row: Vec<u8> = (0..width).map(|x| {
+                let mut bits = 0;
+                for dy in 0..6.min(height - band){
+                    if indices[(band + dy) * width + x] == color{
+                        bits |= 1 << dy;
+                    }
+                }
+                bits
+            }).collect();
+            if row.iter().all(|&bits| bits == 0){
+                continue;
+            }
+            write!(out, "#{}", color).unwrap();
+            let mut x = 0;
+            while x < row.len(){
+                let run = row[x..].iter().take_while(|&&bits| bits == row[x]).count();
+                let character = (63 + row[x]) as char;
+                if run > 3{
+                    write!(out, "!{}{}", run, character).unwrap();
+                }else{
+                    out.extend(std::iter::repeat_n(character, run));
+                }
+                x += run;
+            }
+            out.push('$');
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    out
+}
+
+fn kitty(image: &[u8], width: usize, height: usize) -> String{
+    let data = base64(image);
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(KITTY_CHUNK).collect();
+    let mut out = String::new();
+    for (index, chunk) in chunks.iter().enumerate(){
+        let more = (index + 1 < chunks.len()) as u8;
+        if index == 0{
+            write!(out, "\x1b_Ga=T,f=24,s={},v={},i=1,q=2,C=1,m={};", width, height, more).unwrap();
+        }else{
+            write!(out, "\x1b_Gm={};", more).unwrap();
+        }
+        out.push_str(std::str::from_utf8(chunk).unwrap());
+        out.push_str("\x1b\\");
+    }
+    out
+}
+
+fn base64(data: &[u8]) -> String{
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3){
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let triple = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for i in 0..4{
+            if i <= chunk.len(){
+                out.push(BASE64[(triple >> (18 - i * 6)) as usize & 0x3F] as char);
+            }else{
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_pads() {
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"M"), "TQ==");
+    }
+
+    #[test]
+    fn sixel_encodes_bands() {
+        let image = [0, 0, 0, 255, 255, 255, 0, 0, 0, 255, 255, 255];
+        assert_eq!(encode(Protocol::Sixel, &image, 2, 2, 1), "\x1bPq\"1;1;2;2#0;2;0;0;0#1;2;100;100;100#0B?$#1?B$-\x1b\\");
+    }
+
+    #[test]
+    fn kitty_sends_scaled_rgb() {
+        let image = [255, 0, 0];
+        assert_eq!(encode(Protocol::Kitty, &image, 1, 1, 2), "\x1b_Ga=T,f=24,s=2,v=2,i=1,q=2,C=1,m=0;/wAA/wAA/wAA/wAA\x1b\\");
+    }
+}
@@ -1,47 +1,101 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::keyboard::{Keycode, Mod};
 use sdl2::EventPump;
 
-pub struct Input{
+use yachip_8::frontend::{Command, InputSource};
+
+pub struct SdlInput{
     keys: [u8; 16],
+    bindings: HashMap<Keycode, usize>,
     event_pump: EventPump,
 }
 
-impl Input{
-    pub fn new(sdl_context: &sdl2::Sdl) -> Input{
-        Input{
+impl SdlInput{
+    pub fn new(sdl_context: &sdl2::Sdl) -> SdlInput{
+        SdlInput{
             keys: [0; 16],
+            bindings: DEFAULT_KEYMAP.iter().map(|&(keycode, key)| (keycode, key)).collect(),
             event_pump: sdl_context.event_pump().unwrap(),
         }
     }
+}
+
+impl InputSource for SdlInput{
+    fn reset_bindings(&mut self){
+        self.bindings = DEFAULT_KEYMAP.iter().map(|&(keycode, key)| (keycode, key)).collect();
+        self.keys = [0; 16];
+    }
+
+    fn bind(&mut self, name: &str, key: u8){
+        let keycode = match name {
+            "up" => Keycode::Up,
+            "down" => Keycode::Down,
+            "left" => Keycode::Left,
+            "right" => Keycode::Right,
+            "a" => Keycode::Z,
+            "b" => Keycode::X,
+            _ => return,
+        };
+        self.bindings.insert(keycode, (key & 0xF) as usize);
+    }
 
-    pub fn input_key(&mut self){
+    fn poll(&mut self) -> Vec<Command>{
+        let mut commands = Vec::new();
         for event in self.event_pump.poll_iter() {
             match event {
-                Event::KeyDown { keycode: Some(Keycode::Num0), .. } => self.keys[0] = 1,
-                Event::KeyDown { keycode: Some(Keycode::Num1), .. } => self.keys[1] = 1,
-                Event::KeyDown { keycode: Some(Keycode::Num2), .. } => self.keys[2] = 1,
-                Event::KeyDown { keycode: Some(Keycode::Num3), .. } => self.keys[3] = 1,
-                Event::KeyDown { keycode: Some(Keycode::Num4), .. } => self.keys[4] = 1,
-                Event::KeyDown { keycode: Some(Keycode::Num5), .. } => self.keys[5] = 1,
-                Event::KeyDown { keycode: Some(Keycode::Num6), .. } => self.keys[6] = 1,
-                Event::KeyDown { keycode: Some(Keycode::Num7), .. } => self.keys[7] = 1,
-                Event::KeyDown { keycode: Some(Keycode::Num8), .. } => self.keys[8] = 1,
-                Event::KeyDown { keycode: Some(Keycode::Num9), .. } => self.keys[9] = 1,
-                Event::KeyDown { keycode: Some(Keycode::A), .. } => self.keys[10] = 1,
-                Event::KeyDown { keycode: Some(Keycode::B), .. } => self.keys[11] = 1,
-                Event::KeyDown { keycode: Some(Keycode::C), .. } => self.keys[12] = 1,
-                Event::KeyDown { keycode: Some(Keycode::D), .. } => self.keys[13] = 1,
-                Event::KeyDown { keycode: Some(Keycode::E), .. } => self.keys[14] = 1,
-                Event::KeyDown { keycode: Some(Keycode::F), .. } => self.keys[15] = 1,
+                Event::Quit { .. } => commands.push(Command::Quit),
+                Event::KeyDown { keycode: Some(Keycode::P), repeat: false, .. } => commands.push(Command::NextPalette),
+                Event::KeyDown { keycode: Some(Keycode::F11), repeat: false, .. } => commands.push(Command::ToggleFullscreen),
+                Event::KeyDown { keycode: Some(Keycode::Return), keymod, repeat: false, .. } if keymod.intersects(Mod::LALTMOD | Mod::RALTMOD) => {
+                    commands.push(Command::ToggleFullscreen)
+                },
+                Event::DropFile { filename, .. } => commands.push(Command::Load(PathBuf::from(filename))),
+                Event::KeyDown { keycode: Some(keycode), repeat, .. } => {
+                    if let Some(&key) = self.bindings.get(&keycode) {
+                        self.keys[key] = 1;
+                    }
+                    match keycode {
+                        Keycode::Up => commands.push(Command::Up),
+                        Keycode::Down => commands.push(Command::Down),
+                        Keycode::Return if !repeat => commands.push(Command::Select),
+                        Keycode::Escape if !repeat => commands.push(Command::Back),
+                        _ => {}
+                    }
+                },
+                Event::KeyUp { keycode: Some(keycode), .. } => {
+                    if let Some(&key) = self.bindings.get(&keycode) {
+                        self.keys[key] = 0;
+                    }
+                },
                 _ => {}
             }
         }
+        commands
     }
 
-    pub fn get_keys(&mut self) -> [u8; 16]{
-        self.keys = [0; 16];
-        self.input_key();
+    fn keys(&self) -> [u8; 16]{
         self.keys
     }
 }
+
+const DEFAULT_KEYMAP: [(Keycode, usize); 16] = [
+    (Keycode::Num0, 0),
+    (Keycode::Num1, 1),
+    (Keycode::Num2, 2),
+    (Keycode::Num3, 3),
+    (Keycode::Num4, 4),
+    (Keycode::Num5, 5),
+    (Keycode::Num6, 6),
+    (Keycode::Num7, 7),
+    (Keycode::Num8, 8),
+    (Keycode::Num9, 9),
+    (Keycode::A, 10),
+    (Keycode::B, 11),
+    (Keycode::C, 12),
+    (Keycode::D, 13),
+    (Keycode::E, 14),
+    (Keycode::F, 15),
+];
@@ -0,0 +1,56 @@
+#![allow(clippy::upper_case_acronyms)]
+
+pub mod ram;
+pub mod cpu;
+pub mod display;
+pub mod timer;
+pub mod movie;
+pub mod rng;
+pub mod palette;
+pub mod filter;
+pub mod quirks;
+pub mod timing;
+pub mod machine;
+pub mod platform;
+pub mod fault;
+pub mod rom;
+pub mod database;
+pub mod browser;
+pub mod audio;
+pub mod frontend;
+pub mod graphics;
+pub mod state;
+pub mod environment;
+pub mod unwind;
+pub mod batch;
+pub mod compat;
+pub mod octo;
+
+pub const SCREEN_WIDTH: usize = 64;
+pub const SCREEN_HEIGHT: usize = 32;
+
+pub const CYCLES_PER_FRAME: u32 = 10;
+
+const RAM_SIZE: usize = 4096;
+const REGISTER_COUNT: usize = 16;
+const STACK_SIZE: usize = 16;
+const FONTSET_SIZE: usize = 80;
+
+const FONTSET: [u8; FONTSET_SIZE] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+    0x20, 0x60, 0x20, 0x20, 0x70, // 1
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+    0x90, 0x90, 0xF0, 0x10, 0x10, // 4
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+    0xF0, 0x10, 0x20, 0x40, 0x40, // 7
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+    0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+    0xE0, 0x90, 0x90, 0x90, 0xE0, // D
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+];
@@ -0,0 +1,160 @@
+use crate::cpu::CPU;
+use crate::display::Display;
+use crate::fault::Fault;
+use crate::platform::Platform;
+use crate::quirks::Quirks;
+use crate::ram::RAM;
+use crate::state::{StateReader, StateWriter};
+use crate::timer::Timer;
+use crate::timing::{Timing, VIP_CYCLES_PER_FRAME};
+
+pub struct Machine{
+    cpu: CPU,
+    ram: RAM,
+    display: Display,
+    timer: Timer,
+    timing: Timing,
+    cycle_budget: i64,
+}
+
+impl Machine{
+    pub fn new(rom: &[u8], seed: u64, platform: Platform, quirks: Quirks, timing: Timing) -> Result<Machine, String>{
+        let mut ram = RAM::with_size(platform.memory_size());
+        ram.load_fontset();
+        ram.load_rom(rom, platform.start_address())?;
+        let mut cpu = CPU::new(seed, quirks);
+        cpu.set_program_counter(platform.start_address());
+        Ok(Machine{
+            cpu,
+            ram,
+            display: Display::new(),
+            timer: Timer::new(),
+            timing,
+            cycle_budget: 0,
+        })
+    }
+
+    pub fn cpu(&self) -> &CPU{
+        &self.cpu
+    }
+
+    pub fn ram(&self) -> &RAM{
+        &self.ram
+    }
+
+    pub fn ram_mut(&mut self) -> &mut RAM{
+        &mut self.ram
+    }
+
+    pub fn display(&self) -> &Display{
+        &self.display
+    }
+
+    pub fn timer(&self) -> &Timer{
+        &self.timer
+    }
+
+    pub fn save_state(&self) -> Vec<u8>{
+        let mut writer = StateWriter::new();
+        self.cpu.save_state(&mut writer);
+        self.ram.save_state(&mut writer);
+        self.display.save_state(&mut writer);
+        self.timer.save_state(&mut writer);
+        writer.u64(self.cycle_budget as u64);
+        writer.finish()
+    }
+
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), String>{
+        let mut reader = StateReader::new(data)?;
+        let mut cpu = CPU::new(0, self.cpu.quirks());
+        let mut ram = RAM::with_size(self.ram.size());
+        let mut display = Display::new();
+        let mut timer = Timer::new();
+        cpu.load_state(&mut reader)?;
+        ram.load_state(&mut reader)?;
+        display.load_state(&mut reader)?;
+        timer.load_state(&mut reader)?;
+        let cycle_budget = reader.u64()? as i64;
+        reader.finish()?;
+
+        self.cpu = cpu;
+        self.ram = ram;
+        self.display = display;
+        self.timer = timer;
+        self.cycle_budget = cycle_budget;
+        Ok(())
+    }
+
+    pub fn step(&mut self, keys: &[u8; 16]) -> Result<u32, Fault>{
+        self.cpu.cycle(&mut self.ram, &mut self.display, keys, &mut self.timer)
+    }
+
+    pub fn run_frame(&mut self, keys: &[u8; 16]) -> Result<(), Fault>{
+        match self.timing{
+            Timing::Fixed(cycles) => {
+                for _ in 0..cycles{
+                    self.cpu.cycle(&mut self.ram, &mut self.display, keys, &mut self.timer)?;
+                }
+            },
+            Timing::Vip => {
+                self.cycle_budget += VIP_CYCLES_PER_FRAME as i64;
+                while self.cycle_budget > 0 && !self.cpu.waiting_for_vblank(){
+                    self.cycle_budget -= self.cpu.cycle(&mut self.ram, &mut self.display, keys, &mut self.timer)? as i64;
+                }
+                self.cycle_budget = self.cycle_budget.min(0);
+            },
+        }
+        self.timer.tick();
+        self.cpu.vblank();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CYCLES_PER_FRAME;
+
+    const ROM: [u8; 10] = [0xC0, 0xFF, 0x61, 0x05, 0xF1, 0x15, 0x72, 0x01, 0x12, 0x00];
+
+    #[test]
+    fn load_state_restores_machine() {
+        let mut machine = Machine::new(&ROM, 7, Platform::Chip8, Quirks::default(), Timing::Fixed(CYCLES_PER_FRAME)).unwrap();
+        machine.run_frame(&[0; 16]).unwrap();
+        let state = machine.save_state();
+        for _ in 0..5 {
+            machine.run_frame(&[0; 16]).unwrap();
+        }
+        let expected = machine.save_state();
+
+        machine.load_state(&state).unwrap();
+        assert_eq!(machine.save_state(), state);
+        for _ in 0..5 {
+            machine.run_frame(&[0; 16]).unwrap();
+        }
+        assert_eq!(machine.save_state(), expected);
+    }
+
+    #[test]
+    fn load_state_rejects_bad_data() {
+        let mut machine = Machine::new(&ROM, 7, Platform::Chip8, Quirks::default(), Timing::Fixed(CYCLES_PER_FRAME)).unwrap();
+        let state = machine.save_state();
+        assert!(machine.load_state(&state[..state.len() - 1]).is_err());
+        assert!(machine.load_state(b"not a state").is_err());
+
+        let mut xo_chip = Machine::new(&ROM, 7, Platform::XoChip, Quirks::default(), Timing::Fixed(CYCLES_PER_FRAME)).unwrap();
+        assert!(xo_chip.load_state(&state).is_err());
+        assert_eq!(machine.save_state(), state);
+    }
+
+    #[test]
+    fn vip_timing_carries_overspent_cycles() {
+        let rom = [0x70, 0x01, 0x12, 0x00];
+        let mut machine = Machine::new(&rom, 0, Platform::Chip8, Quirks::default(), Timing::Vip).unwrap();
+        for (frame, adds) in [26, 52, 78].into_iter().enumerate() {
+            machine.run_frame(&[0; 16]).unwrap();
+            assert_eq!(machine.cpu().registers()[0], adds, "frame {}", frame);
+            assert_eq!(machine.cycle_budget, -8 * (frame as i64 + 1));
+        }
+    }
+}
@@ -1,96 +1,445 @@
-use std::fs::read_dir;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
 use std::thread;
-use std::time::Duration;
-use std::io::stdin;
+use std::time::{Duration, Instant};
 
-mod ram;
-mod cpu;
+use rand::random;
+use sha1_smol::Sha1;
+
+#[cfg(feature = "sdl")]
 mod input;
-mod display;
-mod timer;
+#[cfg(feature = "sdl")]
 mod sound;
+mod config;
+#[cfg(feature = "sdl")]
+mod renderer;
+#[cfg(feature = "terminal")]
+mod terminal;
+
+use yachip_8::batch;
+use yachip_8::browser::Browser;
+use yachip_8::compat;
+use yachip_8::display::Display;
+use yachip_8::machine::Machine;
+use yachip_8::movie::Movie;
+use yachip_8::palette::Palette;
+use yachip_8::filter::Filter;
+use yachip_8::frontend::{AudioSink, Command, InputSource, Renderer};
+use yachip_8::graphics;
+use yachip_8::rom::{self, Rom};
+use yachip_8::database::{Database, RomInfo};
+use yachip_8::timing::Timing;
+use yachip_8::{CYCLES_PER_FRAME, SCREEN_HEIGHT};
+
+#[cfg(feature = "sdl")]
+use input::SdlInput;
+use config::{BatchConfig, CompatConfig, Config};
+#[cfg(feature = "sdl")]
+use renderer::SdlRenderer;
+
+pub const DEFAULT_SCALE: u32 = 10;
+
+const FRAME_DURATION: Duration = Duration::from_micros(16_667);
+const ROM_DIRECTORY: &str = "roms/";
+
+struct Game{
+    path: PathBuf,
+    machine: Machine,
+    rom_hash: String,
+    info: Option<RomInfo>,
+    palette: Palette,
+}
+
+impl Game{
+    fn load(path: &Path, entry: Option<&str>, seed: u64, config: &Config) -> Result<Game, String>{
+        let rom = Rom::load(path, |names| entry.and_then(|entry| names.iter().position(|name| name == entry)))?;
+        let rom_hash = Sha1::from(&rom.data).hexdigest();
+        let info = rom.info.or_else(|| {
+            let info = Database::bundled().lookup(&rom_hash);
+            if let Some(info) = &info {
+                println!("Found {} in the rom database", info.title);
+            }
+            info
+        });
+
+        let platform = config.platform
+            .or(info.as_ref().and_then(|info| info.platform))
+            .unwrap_or(rom.platform);
+        let mut quirks = config.quirks;
+        quirks.display_wait = config.display_wait
+            .or(info.as_ref().and_then(|info| info.display_wait))
+            .unwrap_or(false);
+        let timing = match config.timing {
+            Timing::Fixed(_) => Timing::Fixed(config.tickrate
+                .or(info.as_ref().and_then(|info| info.tickrate))
+                .unwrap_or(CYCLES_PER_FRAME)),
+            timing => timing,
+        };
+
+        let machine = Machine::new(&rom.data, seed, platform, quirks, timing)
+            .map_err(|error| format!("{}: {}", path.display(), error))?;
+        println!("Rom loaded.. ({})", platform.name());
+
+        let mut palette = config.palette.clone()
+            .or_else(|| info.as_ref().and_then(RomInfo::palette))
+            .unwrap_or_default();
+        if let Some(color) = config.foreground {
+            palette.set_foreground(color);
+        }
+        if let Some(color) = config.background {
+            palette.set_background(color);
+        }
+
+        Ok(Game{
+            path: path.to_path_buf(),
+            machine,
+            rom_hash,
+            info,
+            palette,
+        })
+    }
+
+    fn title(&self) -> String{
+        match &self.info {
+            Some(info) if info.authors.is_empty() => format!("YACHIP8 - {}", info.title),
+            Some(info) => format!("YACHIP8 - {} by {}", info.title, info.authors.join(", ")),
+            None => "YACHIP8".to_string(),
+        }
+    }
+}
+
+struct Frontend{
+    input: Box<dyn InputSource>,
+    sound: Box<dyn AudioSink>,
+    renderer: Box<dyn Renderer>,
+}
+
+impl Frontend{
+    fn new(config: &Config) -> Frontend{
+        #[cfg(feature = "terminal")]
+        if let Some(output) = config.terminal {
+            return Frontend::terminal(config, output);
+        }
+        Frontend::sdl(config)
+    }
+
+    #[cfg(feature = "terminal")]
+    fn terminal(config: &Config, output: terminal::Output) -> Frontend{
+        let filter = Filter::new(config.persistence, config.scanlines);
+        let renderer = terminal::TerminalRenderer::new(filter, output, config.scale);
+        Frontend{
+            input: Box::new(terminal::TerminalInput::new(renderer.release_events())),
+            sound: Box::new(terminal::Bell::new()),
+            renderer: Box::new(renderer),
+        }
+    }
+
+    #[cfg(feature = "sdl")]
+    fn sdl(config: &Config) -> Frontend{
+        let sdl_context = sdl2::init().unwrap();
+        let filter = Filter::new(config.persistence, config.scanlines);
+        Frontend{
+            input: Box::new(SdlInput::new(&sdl_context)),
+            sound: sound::open(&sdl_context),
+            renderer: Box::new(SdlRenderer::new(&sdl_context, "YACHIP8", filter, config.scale, config.scaling)),
+        }
+    }
+
+    #[cfg(not(feature = "sdl"))]
+    fn sdl(_config: &Config) -> Frontend{
+        eprintln!("built without the sdl feature, run with --headless or a terminal frontend");
+        process::exit(1);
+    }
+
+    fn show_game(&mut self, game: &Game){
+        self.input.reset_bindings();
+        if let Some(info) = &game.info {
+            for (name, key) in &info.keys {
+                self.input.bind(name, *key);
+            }
+        }
+        self.renderer.set_title(&game.title());
+        self.renderer.render(game.machine.display(), &game.palette);
+    }
 
-use ram::RAM;
-use cpu::CPU;
-use input::Input;
-use display::Display;
-use timer::Timer;
-use sound::Sound;
-
-
-pub const SCREEN_WIDTH: usize = 64;
-pub const SCREEN_HEIGHT: usize = 32;
-pub const PIXEL_SIZE: u32 = 10;
-
-const RAM_SIZE: usize = 4096;
-const REGISTER_COUNT: usize = 16;
-const STACK_SIZE: usize = 16;
-const FONTSET_SIZE: usize = 80;
-
-const FONTSET: [u8; FONTSET_SIZE] = [
-    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
-    0x20, 0x60, 0x20, 0x20, 0x70, // 1
-    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
-    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
-    0x90, 0x90, 0xF0, 0x10, 0x10, // 4
-    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
-    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
-    0xF0, 0x10, 0x20, 0x40, 0x40, // 7
-    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
-    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
-    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
-    0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
-    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
-    0xE0, 0x90, 0x90, 0x90, 0xE0, // D
-    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
-    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
-];
+    fn show_browser(&mut self, browser: &Browser, display: &mut Display, palette: &Palette){
+        let title = match browser.selected() {
+            Some(path) => format!("YACHIP8 - {}", path.display()),
+            None => format!("YACHIP8 - {} (no roms)", browser.directory().display()),
+        };
+        self.renderer.set_title(&title);
+        browser.draw(display);
+        self.renderer.render(display, palette);
+    }
+}
 
 fn main(){
+    let mut args = env::args().skip(1).peekable();
+    match args.peek().map(String::as_str) {
+        Some("batch") => {
+            args.next();
+            run_batch(BatchConfig::from_args(args).unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                process::exit(1);
+            }));
+            return;
+        },
+        Some("compat") => {
+            args.next();
+            run_compat(CompatConfig::from_args(args).unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                process::exit(1);
+            }));
+            return;
+        },
+        _ => {},
+    }
+
+    let config = Config::from_args(args).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    });
+
     println!("Intializing...");
 
-    let mut cpu = CPU::new();
-    let mut ram = RAM::new();
+    let mut playback = config.play.as_ref().map(|path| {
+        Movie::load(path).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(1);
+        })
+    });
+
+    let seed = match (&playback, config.seed) {
+        (Some(movie), _) => movie.seed(),
+        (None, Some(seed)) => seed,
+        (None, None) => random(),
+    };
+    println!("Seed: {}", seed);
+
+    let browse_archive = config.rom.as_ref().filter(|path| !config.headless && needs_entry(path));
+    let mut game = config.rom.as_ref().filter(|_| browse_archive.is_none()).map(|path| {
+        Game::load(path, None, seed, &config).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(1);
+        })
+    });
+
+    let mut recording = game.as_ref().and_then(|game| start_movie(game, &playback, &config, seed));
+
+    let mut frontend = if config.headless { None } else { Some(Frontend::new(&config)) };
+    let mut browser = None;
+    let mut browser_display = Display::new();
+    match (&mut frontend, &game) {
+        (Some(frontend), Some(game)) => frontend.show_game(game),
+        (Some(frontend), None) => {
+            let opened = open_browser(browse_archive.map_or(Path::new(ROM_DIRECTORY), PathBuf::as_path));
+            frontend.show_browser(&opened, &mut browser_display, &Palette::new());
+            browser = Some(opened);
+        },
+        _ => {},
+    }
 
-    let sdl_context = sdl2::init().unwrap();
-    let mut display = Display::new();
+    let mut frame: u64 = 0;
+    loop{
+        if config.frames.is_some_and(|frames| frame >= frames) {
+            break;
+        }
+        let frame_start = Instant::now();
 
-    let mut input = Input::new(&sdl_context);
+        let mut keys = [0; 16];
+        if let Some(frontend) = &mut frontend {
+            let mut quit = false;
+            let mut load = None;
+            let mut browser_changed = false;
+            for command in frontend.input.poll() {
+                match command {
+                    Command::Quit => quit = true,
+                    Command::NextPalette => {
+                        if let Some(game) = &mut game {
+                            game.palette = game.palette.next();
+                            println!("Palette: {}", game.palette.name());
+                        }
+                    },
+                    Command::ToggleFullscreen => frontend.renderer.toggle_fullscreen(),
+                    Command::Load(path) => load = Some((path, None)),
+                    Command::Back => match (&browser, &game) {
+                        (Some(_), Some(game)) => {
+                            browser = None;
+                            frontend.show_game(game);
+                        },
+                        (None, Some(game)) => {
+                            let directory = game.path.parent().filter(|directory| directory.is_dir()).unwrap_or(Path::new(ROM_DIRECTORY));
+                            browser = Some(open_browser(directory));
+                            browser_changed = true;
+                        },
+                        _ => {},
+                    },
+                    Command::Up | Command::Down | Command::Select => {
+                        let Some(opened) = &mut browser else { continue };
+                        match command {
+                            Command::Up => opened.up(),
+                            Command::Down => opened.down(),
+                            _ => match (opened.selected_entry(), opened.selected().map(Path::to_path_buf)) {
+                                (Some((archive, entry)), _) => load = Some((archive.to_path_buf(), Some(entry.to_string()))),
+                                (None, Some(path)) if path.is_dir() => *opened = open_browser(&path),
+                                (None, Some(path)) => load = Some((path, None)),
+                                (None, None) => {},
+                            },
+                        }
+                        browser_changed = true;
+                    },
+                }
+            }
+            if quit {
+                break;
+            }
 
-    let mut timer = Timer::new();
-    let mut sound = Sound::new(&sdl_context);
+            if let Some((path, entry)) = load {
+                let movie_active = playback.is_some() || config.record.is_some();
+                if movie_active && game.is_some() {
+                    eprintln!("can't load {} while a movie is recording or playing", path.display());
+                } else if entry.is_none() && needs_entry(&path) {
+                    browser = Some(open_browser(&path));
+                    browser_changed = true;
+                } else {
+                    let load_seed = if movie_active { seed } else { config.seed.unwrap_or_else(random) };
+                    match Game::load(&path, entry.as_deref(), load_seed, &config) {
+                        Ok(loaded) => {
+                            if movie_active {
+                                recording = start_movie(&loaded, &playback, &config, seed);
+                            }
+                            frontend.show_game(&loaded);
+                            game = Some(loaded);
+                            browser = None;
+                        },
+                        Err(error) => eprintln!("{}", error),
+                    }
+                }
+            }
+            if let Some(opened) = &browser {
+                frontend.sound.play(false);
+                if browser_changed {
+                    let palette = game.as_ref().map_or_else(Palette::new, |game| game.palette.clone());
+                    frontend.show_browser(opened, &mut browser_display, &palette);
+                }
+                if let Some(remaining) = FRAME_DURATION.checked_sub(frame_start.elapsed()) {
+                    thread::sleep(remaining);
+                }
+                continue;
+            }
+            keys = frontend.input.keys();
+        }
+        let Some(game) = &mut game else { break };
 
-    ram.load_fontset();
+        if let Some(movie) = &mut playback {
+            match movie.next_frame() {
+                Some(movie_keys) => keys = movie_keys,
+                None if config.headless => break,
+                None => {},
+            }
+        }
+        if let Some(movie) = &mut recording {
+            movie.record_frame(&keys);
+        }
+
+        if let Err(fault) = game.machine.run_frame(&keys) {
+            drop(frontend);
+            eprintln!("error: {}", fault);
+            process::exit(1);
+        }
+        frame += 1;
 
-    let mut roms = Vec::new();
-    for entry in read_dir("roms/").unwrap() {
-        let entry = entry.unwrap();
-        let path = entry.path();
-        if path.is_file() {
-            roms.push(path);
+        if let Some(frontend) = &mut frontend {
+            frontend.sound.update(game.machine.timer());
+            frontend.renderer.render(game.machine.display(), &game.palette);
+            if let Some(remaining) = FRAME_DURATION.checked_sub(frame_start.elapsed()) {
+                thread::sleep(remaining);
+            }
         }
     }
 
-    println!("Available roms:");
-    for (i, rom) in roms.iter().enumerate() {
-        println!("{}: {}", i, rom.file_name().unwrap().to_str().unwrap());
+    if let (true, Some(game)) = (config.headless, &game) {
+        println!("{} frames, framebuffer sha1 {}", frame, Sha1::from(game.machine.display().framebuffer()).hexdigest());
+        if let Some(protocol) = config.dump {
+            let mut filter = Filter::new(config.persistence, config.scanlines);
+            let (width, height) = (filter.width(), filter.height());
+            let image = filter.apply(game.machine.display().framebuffer(), &game.palette);
+            let scale = config.scale as usize * SCREEN_HEIGHT / height;
+            println!("{}", graphics::encode(protocol, image, width, height, scale.max(1)));
+        }
     }
 
-    let mut rom_index = String::new();
-    println!("Select rom:");
-    stdin().read_line(&mut rom_index).unwrap();
-    let rom_index: usize = rom_index.trim().parse().unwrap();
+    if let (Some(path), Some(movie)) = (&config.record, &recording) {
+        movie.save(path).unwrap();
+        println!("Recorded {} frames to {}", movie.frame_count(), path.display());
+    }
+}
 
-    ram.load_rom(&roms[rom_index]);
+fn run_batch(config: BatchConfig){
+    let mut jobs = fs::read_to_string(&config.manifest)
+        .map_err(|error| format!("{}: {}", config.manifest.display(), error))
+        .and_then(|json| batch::parse_manifest(&json, config.manifest.parent().unwrap_or(Path::new(""))))
+        .unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(1);
+        });
+    for job in &mut jobs {
+        if job.frames.is_none() && job.movie.is_none() {
+            job.frames = config.frames;
+        }
+    }
 
-    println!("Rom loaded..");
+    let outcomes = batch::run(&jobs, config.threads);
+    println!("{}", batch::report(&jobs, &outcomes));
+    if outcomes.iter().any(|outcome| outcome.error.is_some()) {
+        process::exit(1);
+    }
+}
 
-    let mut canvas = display.create_window_and_draw_to_screen(&sdl_context);
+fn run_compat(config: CompatConfig){
+    let roms = compat::roms(&config.directory).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    });
+    let checks: Vec<(&PathBuf, &'static compat::Profile)> = roms.iter()
+        .flat_map(|rom| compat::PROFILES.iter().map(move |profile| (rom, profile)))
+        .collect();
+    let reports = batch::parallel(&checks, config.threads, |(rom, profile)| compat::check(rom, profile, config.frames));
 
-    loop{
-        canvas.present();
-        cpu.cycle(&mut ram, &mut display, &mut input, &mut timer, &mut sound, &mut canvas);
-        sound.play_sound(&mut timer);
-        thread::sleep(Duration::from_millis(1));
+    if config.json {
+        let reports: Vec<_> = reports.iter().map(compat::Report::to_json).collect();
+        println!("{}", serde_json::to_string_pretty(&reports).unwrap());
+        return;
+    }
+    for rom in &roms {
+        println!("{}", rom.file_name().unwrap_or_default().to_string_lossy());
+        for report in reports.iter().filter(|report| &report.rom == rom) {
+            println!("    {:<8} {:<15} {}", report.profile, report.status(), report.summary());
+        }
     }
 }
+
+fn open_browser(directory: &Path) -> Browser{
+    Browser::new(directory)
+        .or_else(|_| Browser::new(Path::new(".")))
+        .unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(1);
+        })
+}
+
+fn start_movie(game: &Game, playback: &Option<Movie>, config: &Config, seed: u64) -> Option<Movie>{
+    if let Some(movie) = playback {
+        if movie.rom_hash() != game.rom_hash {
+            eprintln!("warning: movie was recorded with a different rom ({})", movie.rom_hash());
+        }
+        println!("Playing back {} frames", movie.frame_count());
+    }
+    config.record.as_ref().map(|_| Movie::new(game.rom_hash.clone(), seed))
+}
+
+fn needs_entry(path: &Path) -> bool{
+    rom::is_archive(path) && Rom::entries(path).is_ok_and(|names| names.len() > 1)
+}
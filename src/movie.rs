@@ -0,0 +1,190 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+const MOVIE_HEADER: &str = "YACHIP8-MOVIE 1";
+
+pub struct Movie{
+    rom_hash: String,
+    seed: u64,
+    frames: Vec<u16>,
+    position: usize,
+}
+
+impl Movie{
+    pub fn new(rom_hash: String, seed: u64) -> Movie{
+        Movie{
+            rom_hash,
+            seed,
+            frames: Vec::new(),
+            position: 0,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Movie, String>{
+        let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut lines = BufReader::new(file).lines();
+
+        let mut next_line = || -> Result<String, String>{
+            match lines.next(){
+                Some(line) => line.map_err(|e| e.to_string()),
+                None => Err(format!("{}: unexpected end of movie", path.display())),
+            }
+        };
+
+        if next_line()? != MOVIE_HEADER{
+            return Err(format!("{}: not a movie file", path.display()));
+        }
+        let rom_hash = match next_line()?.strip_prefix("rom "){
+            Some(hash) => hash.to_string(),
+            None => return Err(format!("{}: missing rom hash", path.display())),
+        };
+        let seed = match next_line()?.strip_prefix("seed "){
+            Some(seed) => seed.parse().map_err(|_| format!("{}: invalid seed", path.display()))?,
+            None => return Err(format!("{}: missing seed", path.display())),
+        };
+
+        let mut movie = Movie::new(rom_hash, seed);
+        for line in lines{
+            let line = line.map_err(|e| e.to_string())?;
+            let line = line.trim();
+            if line.is_empty(){
+                continue;
+            }
+            let mask = u16::from_str_radix(line, 16)
+                .map_err(|_| format!("{}: invalid frame \"{}\"", path.display(), line))?;
+            movie.frames.push(mask);
+        }
+        Ok(movie)
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()>{
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", MOVIE_HEADER)?;
+        writeln!(writer, "rom {}", self.rom_hash)?;
+        writeln!(writer, "seed {}", self.seed)?;
+        for mask in &self.frames{
+            writeln!(writer, "{:04x}", mask)?;
+        }
+        writer.flush()
+    }
+
+    pub fn rom_hash(&self) -> &str{
+        &self.rom_hash
+    }
+
+    pub fn seed(&self) -> u64{
+        self.seed
+    }
+
+    pub fn frame_count(&self) -> usize{
+        self.frames.len()
+    }
+
+    pub fn record_frame(&mut self, keys: &[u8; 16]){
+        let mut mask = 0u16;
+        for (i, &key) in keys.iter().enumerate(){
+            if key != 0{
+                mask |= 1 << i;
+            }
+        }
+        self.frames.push(mask);
+    }
+
+    pub fn next_frame(&mut self) -> Option<[u8; 16]>{
+        let mask = *self.frames.get(self.position)?;
+        self.position += 1;
+        Some(keys_from_mask(mask))
+    }
+}
+
+pub fn keys_from_mask(mask: u16) -> [u8; 16]{
+    let mut keys = [0; 16];
+    for (i, key) in keys.iter_mut().enumerate(){
+        *key = ((mask >> i) & 0x1) as u8;
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::machine::Machine;
+    use crate::platform::Platform;
+    use crate::quirks::Quirks;
+    use crate::timing::Timing;
+    use crate::CYCLES_PER_FRAME;
+
+    fn temp_movie(name: &str, contents: Option<&str>) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("yachip_8_movie_{}_{}.txt", name, std::process::id()));
+        if let Some(contents) = contents {
+            fs::write(&path, contents).unwrap();
+        }
+        path
+    }
+
+    fn load_error(name: &str, contents: &str) -> String {
+        let path = temp_movie(name, Some(contents));
+        let error = Movie::load(&path).err().unwrap();
+        fs::remove_file(&path).unwrap();
+        error
+    }
+
+    #[test]
+    fn save_then_load_round_trips() {
+        let mut movie = Movie::new("abc123".to_string(), 42);
+        movie.record_frame(&[0; 16]);
+        movie.record_frame(&keys_from_mask(0x8001));
+        let path = temp_movie("round_trip", None);
+        movie.save(&path).unwrap();
+
+        let mut loaded = Movie::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.rom_hash(), "abc123");
+        assert_eq!(loaded.seed(), 42);
+        assert_eq!(loaded.frame_count(), 2);
+        assert_eq!(loaded.next_frame(), Some([0; 16]));
+        assert_eq!(loaded.next_frame(), Some(keys_from_mask(0x8001)));
+        assert_eq!(loaded.next_frame(), None);
+    }
+
+    #[test]
+    fn load_rejects_malformed_movies() {
+        assert!(load_error("header", "YACHIP8-MOVIE 2\nrom a\nseed 1\n").contains("not a movie file"));
+        assert!(load_error("hash", "YACHIP8-MOVIE 1\nseed 1\n").contains("missing rom hash"));
+        assert!(load_error("missing_seed", "YACHIP8-MOVIE 1\nrom a\nframes\n").contains("missing seed"));
+        assert!(load_error("seed", "YACHIP8-MOVIE 1\nrom a\nseed -1\n").contains("invalid seed"));
+        assert!(load_error("frame", "YACHIP8-MOVIE 1\nrom a\nseed 1\n0001\nxyz\n").contains("invalid frame \"xyz\""));
+        assert!(load_error("truncated", "YACHIP8-MOVIE 1\nrom a\n").contains("unexpected end of movie"));
+    }
+
+    #[test]
+    fn replay_reproduces_recording() {
+        // Draws a random sprite wherever the pressed key's value and a random byte put it.
+        let rom = [0xC0, 0xFF, 0xF1, 0x0A, 0xA0, 0x00, 0xD0, 0x15, 0x12, 0x00];
+        let run = |movie: &mut Movie, recording: bool| {
+            let mut machine = Machine::new(&rom, movie.seed(), Platform::Chip8, Quirks::default(), Timing::Fixed(CYCLES_PER_FRAME)).unwrap();
+            for frame in 0..30u16 {
+                let keys = if recording {
+                    let keys = keys_from_mask(if frame % 3 == 0 { 1 << (frame % 16) } else { 0 });
+                    movie.record_frame(&keys);
+                    keys
+                } else {
+                    movie.next_frame().unwrap()
+                };
+                machine.run_frame(&keys).unwrap();
+            }
+            machine.save_state()
+        };
+
+        let mut movie = Movie::new("rom".to_string(), 1234);
+        let recorded = run(&mut movie, true);
+        let path = temp_movie("replay", None);
+        movie.save(&path).unwrap();
+        let mut loaded = Movie::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(run(&mut loaded, false), recorded);
+        assert_eq!(loaded.next_frame(), None);
+    }
+}
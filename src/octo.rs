@@ -0,0 +1,879 @@
+use std::collections::{HashMap, VecDeque};
+use std::f64::consts::{E, PI};
+
+use serde_json::Value;
+
+use crate::database::RomInfo;
+use crate::palette::parse_color;
+use crate::platform::Platform;
+
+const START_ADDRESS: usize = 0x200;
+const MEMORY_SIZE: usize = 0x10000;
+
+pub struct Cartridge{
+    pub program: String,
+    pub options: Value,
+}
+
+impl Cartridge{
+    // Octo hides the payload in the low two bits of every pixel's palette index, four pixels
+    // per byte with the high bits first, across all frames: a 32-bit big-endian length and
+    // then that many bytes of UTF-8 json holding the program source and its options.
+    pub fn decode(gif: &[u8]) -> Result<Cartridge, String>{
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::Indexed);
+        let mut decoder = options.read_info(gif).map_err(|e| e.to_string())?;
+        let mut pixels = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().map_err(|e| e.to_string())?{
+            pixels.extend(frame.buffer.iter().map(|index| index & 0x3));
+        }
+
+        let mut bytes = pixels.chunks_exact(4).map(|bits| bits[0] << 6 | bits[1] << 4 | bits[2] << 2 | bits[3]);
+        let length = bytes.by_ref().take(4).fold(0usize, |length, byte| length << 8 | byte as usize);
+        let payload: Vec<u8> = bytes.take(length).collect();
+        if payload.len() != length{
+            return Err("not an Octo cartridge".to_string());
+        }
+        let json: Value = serde_json::from_slice(&payload).map_err(|_| "not an Octo cartridge".to_string())?;
+        let program = json["program"].as_str().ok_or("Octo cartridge has no program")?.to_string();
+        Ok(Cartridge{
+            program,
+            options: json["options"].clone(),
+        })
+    }
+
+    pub fn platform(&self, size: usize) -> Platform{
+        match self.options["maxSize"].as_u64(){
+            Some(3216) => Platform::Chip8,
+            Some(3583) => Platform::SuperChip,
+            Some(65024) => Platform::XoChip,
+            _ if size > 0xE00 => Platform::XoChip,
+            _ => Platform::Chip8,
+        }
+    }
+
+    pub fn info(&self, title: &str, platform: Platform) -> RomInfo{
+        let colors = ["backgroundColor", "fillColor", "fillColor2", "blendColor"].iter()
+            .map_while(|name| parse_color(self.options[name].as_str()?).ok())
+            .collect();
+        RomInfo{
+            title: title.to_string(),
+            authors: Vec::new(),
+            platform: Some(platform),
+            display_wait: self.options["vBlankQuirks"].as_bool(),
+            tickrate: self.options["tickrate"].as_u64().and_then(|tickrate| u32::try_from(tickrate).ok()),
+            colors,
+            keys: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Token{
+    text: String,
+    line: usize,
+}
+
+fn tokenize(source: &str) -> Vec<Token>{
+    let mut tokens = Vec::new();
+    for (number, line) in source.lines().enumerate(){
+        let code = line.split('#').next().unwrap_or("");
+        tokens.extend(code.split_whitespace().map(|text| Token{ text: text.to_string(), line: number + 1 }));
+    }
+    tokens
+}
+
+fn parse_number(text: &str) -> Option<f64>{
+    let (negative, digits) = match text.strip_prefix('-'){
+        Some(digits) => (true, digits),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let value = if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")){
+        i64::from_str_radix(hex, 16).ok()? as f64
+    }else if let Some(binary) = digits.strip_prefix("0b").or_else(|| digits.strip_prefix("0B")){
+        i64::from_str_radix(binary, 2).ok()? as f64
+    }else if digits.starts_with(|digit: char| digit.is_ascii_digit()){
+        digits.parse().ok()?
+    }else{
+        return None;
+    };
+    Some(if negative { -value } else { value })
+}
+
+#[derive(Clone, Copy)]
+enum Patch{
+    Short,
+    Long,
+    Unpack,
+    UnpackLong,
+}
+
+#[derive(Clone, Copy)]
+enum Operand{
+    Register(u8),
+    Byte(u8),
+}
+
+struct Condition{
+    register: u8,
+    operator: String,
+    operand: Option<Operand>,
+}
+
+struct Macro{
+    arguments: Vec<String>,
+    body: Vec<Token>,
+    calls: usize,
+}
+
+struct Assembler{
+    tokens: VecDeque<Token>,
+    line: usize,
+    rom: Vec<u8>,
+    here: usize,
+    end: usize,
+    labels: HashMap<String, u16>,
+    constants: HashMap<String, f64>,
+    aliases: HashMap<String, u8>,
+    macros: HashMap<String, Macro>,
+    protos: HashMap<String, Vec<(usize, Patch)>>,
+    branches: Vec<usize>,
+    loops: Vec<(usize, Vec<usize>)>,
+}
+
+pub fn assemble(source: &str) -> Result<Vec<u8>, String>{
+    let mut assembler = Assembler{
+        tokens: tokenize(source).into(),
+        line: 0,
+        rom: vec![0; MEMORY_SIZE],
+        here: START_ADDRESS + 2,
+        end: START_ADDRESS + 2,
+        labels: HashMap::new(),
+        constants: HashMap::new(),
+        aliases: HashMap::new(),
+        macros: HashMap::new(),
+        protos: HashMap::new(),
+        branches: Vec::new(),
+        loops: Vec::new(),
+    };
+    assembler.run().map_err(|error| format!("line {}: {}", assembler.line, error))?;
+    Ok(assembler.rom[START_ADDRESS..assembler.end].to_vec())
+}
+
+impl Assembler{
+    fn run(&mut self) -> Result<(), String>{
+        while !self.tokens.is_empty(){
+            self.statement()?;
+        }
+        if !self.branches.is_empty(){
+            return Err("'begin' without a matching 'end'".to_string());
+        }
+        if !self.loops.is_empty(){
+            return Err("'loop' without a matching 'again'".to_string());
+        }
+        if let Some(name) = self.protos.keys().min(){
+            return Err(format!("undefined name '{}'", name));
+        }
+        let main = *self.labels.get("main").ok_or("the program has no main label")?;
+        self.here = START_ADDRESS;
+        self.instruction(0x1000 | main)
+    }
+
+    fn next(&mut self) -> Result<String, String>{
+        let token = self.tokens.pop_front().ok_or("unexpected end of program")?;
+        self.line = token.line;
+        Ok(token.text)
+    }
+
+    fn peek(&self) -> Option<&str>{
+        self.tokens.front().map(|token| token.text.as_str())
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), String>{
+        let token = self.next()?;
+        if token != expected{
+            return Err(format!("expected '{}', got '{}'", expected, token));
+        }
+        Ok(())
+    }
+
+    fn byte(&mut self, value: u8) -> Result<(), String>{
+        if self.here >= MEMORY_SIZE{
+            return Err("program does not fit in memory".to_string());
+        }
+        self.rom[self.here] = value;
+        self.here += 1;
+        self.end = self.end.max(self.here);
+        Ok(())
+    }
+
+    fn instruction(&mut self, opcode: u16) -> Result<(), String>{
+        self.byte((opcode >> 8) as u8)?;
+        self.byte(opcode as u8)
+    }
+
+    fn statement(&mut self) -> Result<(), String>{
+        let token = self.next()?;
+        if let Some(register) = self.register(&token){
+            return self.register_statement(register);
+        }
+        match token.as_str(){
+            ":" => {
+                let name = self.next()?;
+                self.define_label(name, self.here)
+            },
+            ":next" => {
+                let name = self.next()?;
+                self.define_label(name, self.here + 1)
+            },
+            ":const" => {
+                let name = self.next()?;
+                let token = self.next()?;
+                let value = self.value(&token)?;
+                self.constants.insert(name, value);
+                Ok(())
+            },
+            ":calc" => {
+                let name = self.next()?;
+                self.expect("{")?;
+                let value = self.expression()?;
+                self.expect("}")?;
+                self.constants.insert(name, value);
+                Ok(())
+            },
+            ":alias" => {
+                let name = self.next()?;
+                let token = self.next()?;
+                let register = self.register(&token).ok_or_else(|| format!("'{}' is not a register", token))?;
+                self.aliases.insert(name, register);
+                Ok(())
+            },
+            ":org" => {
+                let token = self.next()?;
+                let address = self.value(&token)?;
+                if !(0.0..MEMORY_SIZE as f64).contains(&address){
+                    return Err(format!("address {} is out of range", address));
+                }
+                self.here = address as usize;
+                Ok(())
+            },
+            ":byte" => {
+                let value = if self.peek() == Some("{"){
+                    self.next()?;
+                    let value = self.expression()?;
+                    self.expect("}")?;
+                    value
+                }else{
+                    let token = self.next()?;
+                    self.value(&token)?
+                };
+                let value = self.fit(value, -128.0, 255.0)?;
+                self.byte(value as u8)
+            },
+            ":pointer" => {
+                let token = self.next()?;
+                self.address(&token, Patch::Long, 0)
+            },
+            ":call" => {
+                let token = self.next()?;
+                self.address(&token, Patch::Short, 0x2000)
+            },
+            ":unpack" => {
+                let token = self.next()?;
+                let (high, patch) = match token.as_str(){
+                    "long" => (0, Patch::UnpackLong),
+                    _ => (self.fit(self.value(&token)?, 0.0, 15.0)? as u16, Patch::Unpack),
+                };
+                let token = self.next()?;
+                let at = self.here;
+                self.instruction(0x6000 | high << 4)?;
+                self.instruction(0x6100)?;
+                self.reference(&token, at, patch)
+            },
+            ":macro" => self.define_macro(),
+            ":breakpoint" => self.next().map(drop),
+            ":monitor" => self.next().and_then(|_| self.next()).map(drop),
+            "clear" => self.instruction(0x00E0),
+            "return" | ";" => self.instruction(0x00EE),
+            "exit" => self.instruction(0x00FD),
+            "lores" => self.instruction(0x00FE),
+            "hires" => self.instruction(0x00FF),
+            "scroll-left" => self.instruction(0x00FC),
+            "scroll-right" => self.instruction(0x00FB),
+            "scroll-down" => {
+                let rows = self.nybble()?;
+                self.instruction(0x00C0 | rows)
+            },
+            "scroll-up" => {
+                let rows = self.nybble()?;
+                self.instruction(0x00D0 | rows)
+            },
+            "audio" => self.instruction(0xF002),
+            "plane" => {
+                let planes = self.nybble()?;
+                self.instruction(0xF001 | planes << 8)
+            },
+            "bcd" => self.register_instruction(0xF033),
+            "saveflags" => self.register_instruction(0xF075),
+            "loadflags" => self.register_instruction(0xF085),
+            "save" => self.memory_instruction(0xF055, 0x5002),
+            "load" => self.memory_instruction(0xF065, 0x5003),
+            "sprite" => {
+                let x = self.expect_register()?;
+                let y = self.expect_register()?;
+                let height = self.nybble()?;
+                self.instruction(0xD000 | (x as u16) << 8 | (y as u16) << 4 | height)
+            },
+            "jump" => {
+                let token = self.next()?;
+                self.address(&token, Patch::Short, 0x1000)
+            },
+            "jump0" => {
+                let token = self.next()?;
+                self.address(&token, Patch::Short, 0xB000)
+            },
+            "native" => {
+                let token = self.next()?;
+                self.address(&token, Patch::Short, 0x0000)
+            },
+            "delay" | "buzzer" | "pitch" => {
+                self.expect(":=")?;
+                let opcode = match token.as_str(){
+                    "delay" => 0xF015,
+                    "buzzer" => 0xF018,
+                    _ => 0xF03A,
+                };
+                self.register_instruction(opcode)
+            },
+            "i" => self.index_statement(),
+            "if" => {
+                let condition = self.condition()?;
+                match self.next()?.as_str(){
+                    "then" => self.skip_unless(condition, false),
+                    "begin" => {
+                        self.skip_unless(condition, true)?;
+                        self.branches.push(self.here);
+                        self.instruction(0x1000)
+                    },
+                    token => Err(format!("expected 'then' or 'begin', got '{}'", token)),
+                }
+            },
+            "else" => {
+                let branch = self.branches.pop().ok_or("'else' without 'begin'")?;
+                self.branches.push(self.here);
+                self.instruction(0x1000)?;
+                self.patch(branch, self.here as u16, Patch::Short)
+            },
+            "end" => {
+                let branch = self.branches.pop().ok_or("'end' without 'begin'")?;
+                self.patch(branch, self.here as u16, Patch::Short)
+            },
+            "loop" => {
+                self.loops.push((self.here, Vec::new()));
+                Ok(())
+            },
+            "while" => {
+                let condition = self.condition()?;
+                self.skip_unless(condition, true)?;
+                let here = self.here;
+                self.loops.last_mut().ok_or("'while' outside a loop")?.1.push(here);
+                self.instruction(0x1000)
+            },
+            "again" => {
+                let (start, exits) = self.loops.pop().ok_or("'again' without 'loop'")?;
+                self.instruction(0x1000 | start as u16)?;
+                for exit in exits{
+                    self.patch(exit, self.here as u16, Patch::Short)?;
+                }
+                Ok(())
+            },
+            _ if self.macros.contains_key(&token) => self.expand_macro(&token),
+            _ if token.starts_with(':') => Err(format!("unsupported directive '{}'", token)),
+            _ => match self.constant(&token){
+                Some(value) => {
+                    let value = self.fit(value, -128.0, 255.0)?;
+                    self.byte(value as u8)
+                },
+                None => self.address(&token, Patch::Short, 0x2000),
+            },
+        }
+    }
+
+    fn register_statement(&mut self, x: u8) -> Result<(), String>{
+        let x = (x as u16) << 8;
+        let operator = self.next()?;
+        let token = self.next()?;
+        let operand = match (operator.as_str(), token.as_str()){
+            (":=", "random") => {
+                let mask = self.byte_value()?;
+                return self.instruction(0xC000 | x | mask as u16);
+            },
+            (":=", "key") => return self.instruction(0xF00A | x),
+            (":=", "delay") => return self.instruction(0xF007 | x),
+            _ => self.operand(&token)?,
+        };
+        let opcode = match (operator.as_str(), operand){
+            (":=", Operand::Byte(value)) => 0x6000 | value as u16,
+            ("+=", Operand::Byte(value)) => 0x7000 | value as u16,
+            ("-=", Operand::Byte(value)) => 0x7000 | value.wrapping_neg() as u16,
+            (":=", Operand::Register(y)) => 0x8000 | (y as u16) << 4,
+            ("|=", Operand::Register(y)) => 0x8001 | (y as u16) << 4,
+            ("&=", Operand::Register(y)) => 0x8002 | (y as u16) << 4,
+            ("^=", Operand::Register(y)) => 0x8003 | (y as u16) << 4,
+            ("+=", Operand::Register(y)) => 0x8004 | (y as u16) << 4,
+            ("-=", Operand::Register(y)) => 0x8005 | (y as u16) << 4,
+            (">>=", Operand::Register(y)) => 0x8006 | (y as u16) << 4,
+            ("=-", Operand::Register(y)) => 0x8007 | (y as u16) << 4,
+            ("<<=", Operand::Register(y)) => 0x800E | (y as u16) << 4,
+            _ => return Err(format!("can't use '{} {}' on a register", operator, token)),
+        };
+        self.instruction(opcode | x)
+    }
+
+    fn index_statement(&mut self) -> Result<(), String>{
+        match self.next()?.as_str(){
+            "+=" => self.register_instruction(0xF01E),
+            ":=" => match self.next()?.as_str(){
+                "hex" => self.register_instruction(0xF029),
+                "bighex" => self.register_instruction(0xF030),
+                "long" => {
+                    self.instruction(0xF000)?;
+                    let token = self.next()?;
+                    self.address(&token, Patch::Long, 0)
+                },
+                token => {
+                    let token = token.to_string();
+                    self.address(&token, Patch::Short, 0xA000)
+                },
+            },
+            token => Err(format!("can't use '{}' on i", token)),
+        }
+    }
+
+    fn memory_instruction(&mut self, single: u16, range: u16) -> Result<(), String>{
+        let x = self.expect_register()? as u16;
+        if self.peek() != Some("-"){
+            return self.instruction(single | x << 8);
+        }
+        self.next()?;
+        let y = self.expect_register()? as u16;
+        self.instruction(range | x << 8 | y << 4)
+    }
+
+    fn register_instruction(&mut self, opcode: u16) -> Result<(), String>{
+        let x = self.expect_register()?;
+        self.instruction(opcode | (x as u16) << 8)
+    }
+
+    fn condition(&mut self) -> Result<Condition, String>{
+        let register = self.expect_register()?;
+        let operator = self.next()?;
+        let operand = match operator.as_str(){
+            "key" | "-key" => None,
+            "==" | "!=" | "<" | ">" | "<=" | ">=" => {
+                let token = self.next()?;
+                Some(self.operand(&token)?)
+            },
+            _ => return Err(format!("unknown comparison '{}'", operator)),
+        };
+        Ok(Condition{ register, operator, operand })
+    }
+
+    // Emits the instructions that skip the next one unless the condition holds,
+    // or unless it doesn't when `negate` is set.
+    fn skip_unless(&mut self, condition: Condition, negate: bool) -> Result<(), String>{
+        let Condition{ register: x, operator, operand } = condition;
+        let operator = match (negate, operator.as_str()){
+            (false, operator) => operator,
+            (true, "==") => "!=",
+            (true, "!=") => "==",
+            (true, "key") => "-key",
+            (true, "-key") => "key",
+            (true, "<") => ">=",
+            (true, ">=") => "<",
+            (true, ">") => "<=",
+            (true, _) => ">",
+        };
+        let x16 = (x as u16) << 8;
+        match (operator, operand){
+            ("key", _) => self.instruction(0xE0A1 | x16),
+            ("-key", _) => self.instruction(0xE09E | x16),
+            ("==", Some(Operand::Byte(value))) => self.instruction(0x4000 | x16 | value as u16),
+            ("!=", Some(Operand::Byte(value))) => self.instruction(0x3000 | x16 | value as u16),
+            ("==", Some(Operand::Register(y))) => self.instruction(0x9000 | x16 | (y as u16) << 4),
+            ("!=", Some(Operand::Register(y))) => self.instruction(0x5000 | x16 | (y as u16) << 4),
+            (operator, Some(operand)) => {
+                // vf ends up holding the no-borrow flag of x - operand for < and >=,
+                // and of operand - x for > and <=.
+                let operand_first = operator == ">" || operator == "<=";
+                match (operand, operand_first){
+                    (Operand::Register(y), false) => {
+                        self.instruction(0x8F00 | (x as u16) << 4)?;
+                        self.instruction(0x8F05 | (y as u16) << 4)?;
+                    },
+                    (Operand::Register(y), true) => {
+                        self.instruction(0x8F00 | (y as u16) << 4)?;
+                        self.instruction(0x8F05 | (x as u16) << 4)?;
+                    },
+                    (Operand::Byte(value), false) => {
+                        self.instruction(0x6F00 | value as u16)?;
+                        self.instruction(0x8F07 | (x as u16) << 4)?;
+                    },
+                    (Operand::Byte(value), true) => {
+                        self.instruction(0x6F00 | value as u16)?;
+                        self.instruction(0x8F05 | (x as u16) << 4)?;
+                    },
+                }
+                let holds_without_borrow = operator == ">=" || operator == "<=";
+                self.instruction(if holds_without_borrow { 0x3F00 } else { 0x3F01 })
+            },
+            _ => Err("missing operand".to_string()),
+        }
+    }
+
+    fn define_label(&mut self, name: String, address: usize) -> Result<(), String>{
+        if self.labels.contains_key(&name){
+            return Err(format!("label '{}' is defined twice", name));
+        }
+        if self.register(&name).is_some() || parse_number(&name).is_some(){
+            return Err(format!("'{}' can't be a label", name));
+        }
+        let address = address as u16;
+        for (at, patch) in self.protos.remove(&name).unwrap_or_default(){
+            self.patch(at, address, patch)?;
+        }
+        self.labels.insert(name, address);
+        Ok(())
+    }
+
+    fn define_macro(&mut self) -> Result<(), String>{
+        let name = self.next()?;
+        let mut arguments = Vec::new();
+        loop{
+            let token = self.next()?;
+            if token == "{"{
+                break;
+            }
+            arguments.push(token);
+        }
+        let mut body = Vec::new();
+        let mut depth = 1;
+        loop{
+            let token = self.tokens.pop_front().ok_or("unterminated macro")?;
+            match token.text.as_str(){
+                "{" => depth += 1,
+                "}" => depth -= 1,
+                _ => {},
+            }
+            if depth == 0{
+                break;
+            }
+            body.push(token);
+        }
+        self.macros.insert(name, Macro{ arguments, body, calls: 0 });
+        Ok(())
+    }
+
+    fn expand_macro(&mut self, name: &str) -> Result<(), String>{
+        let count = self.macros[name].arguments.len();
+        let mut values = HashMap::new();
+        for index in 0..count{
+            let value = self.next()?;
+            values.insert(self.macros[name].arguments[index].clone(), value);
+        }
+        let definition = self.macros.get_mut(name).unwrap();
+        values.insert("CALLS".to_string(), definition.calls.to_string());
+        definition.calls += 1;
+        for token in definition.body.iter().rev(){
+            let text = values.get(&token.text).cloned().unwrap_or_else(|| token.text.clone());
+            self.tokens.push_front(Token{ text, line: token.line });
+        }
+        Ok(())
+    }
+
+    fn address(&mut self, token: &str, patch: Patch, opcode: u16) -> Result<(), String>{
+        let at = self.here;
+        self.instruction(opcode)?;
+        self.reference(token, at, patch)
+    }
+
+    fn reference(&mut self, token: &str, at: usize, patch: Patch) -> Result<(), String>{
+        match self.constant(token).or_else(|| self.labels.get(token).map(|&address| address as f64)){
+            Some(address) => {
+                let address = self.fit(address, 0.0, (MEMORY_SIZE - 1) as f64)?;
+                self.patch(at, address as u16, patch)
+            },
+            None if parse_number(token).is_none() && self.register(token).is_none() => {
+                self.protos.entry(token.to_string()).or_default().push((at, patch));
+                Ok(())
+            },
+            None => Err(format!("'{}' is not an address", token)),
+        }
+    }
+
+    fn patch(&mut self, at: usize, address: u16, patch: Patch) -> Result<(), String>{
+        let (high, low) = match patch{
+            Patch::Short if address > 0xFFF => return Err(format!("address 0x{:x} is past 0xFFF, use 'i := long'", address)),
+            Patch::Short => (at, at + 1),
+            Patch::Long => (at, at + 1),
+            Patch::Unpack | Patch::UnpackLong => (at + 1, at + 3),
+        };
+        self.rom[high] = match patch{
+            Patch::Short | Patch::Unpack => self.rom[high] & 0xF0 | (address >> 8 & 0xF) as u8,
+            Patch::Long | Patch::UnpackLong => (address >> 8) as u8,
+        };
+        self.rom[low] = address as u8;
+        Ok(())
+    }
+
+    fn register(&self, token: &str) -> Option<u8>{
+        if let Some(&register) = self.aliases.get(token){
+            return Some(register);
+        }
+        let digit = token.strip_prefix('v').or_else(|| token.strip_prefix('V'))?;
+        if digit.len() != 1{
+            return None;
+        }
+        u8::from_str_radix(digit, 16).ok()
+    }
+
+    fn expect_register(&mut self) -> Result<u8, String>{
+        let token = self.next()?;
+        self.register(&token).ok_or_else(|| format!("'{}' is not a register", token))
+    }
+
+    fn operand(&mut self, token: &str) -> Result<Operand, String>{
+        match self.register(token){
+            Some(register) => Ok(Operand::Register(register)),
+            None => Ok(Operand::Byte(self.fit(self.value(token)?, -128.0, 255.0)? as u8)),
+        }
+    }
+
+    fn byte_value(&mut self) -> Result<u8, String>{
+        let token = self.next()?;
+        Ok(self.fit(self.value(&token)?, -128.0, 255.0)? as u8)
+    }
+
+    fn nybble(&mut self) -> Result<u16, String>{
+        let token = self.next()?;
+        Ok(self.fit(self.value(&token)?, 0.0, 15.0)? as u16)
+    }
+
+    fn fit(&self, value: f64, low: f64, high: f64) -> Result<i64, String>{
+        let value = value.floor();
+        if value < low || value > high{
+            return Err(format!("{} does not fit between {} and {}", value, low, high));
+        }
+        Ok(value as i64 & 0xFFFF)
+    }
+
+    fn constant(&self, token: &str) -> Option<f64>{
+        parse_number(token).or_else(|| self.constants.get(token).copied())
+    }
+
+    fn value(&self, token: &str) -> Result<f64, String>{
+        self.constant(token)
+            .or_else(|| self.labels.get(token).map(|&address| address as f64))
+            .ok_or_else(|| format!("undefined name '{}'", token))
+    }
+
+    // Octo evaluates :calc expressions right to left without operator precedence.
+    fn expression(&mut self) -> Result<f64, String>{
+        let left = self.term()?;
+        let Some(operator) = self.peek().map(str::to_string) else { return Ok(left) };
+        let operation: fn(f64, f64) -> f64 = match operator.as_str(){
+            "+" => |a, b| a + b,
+            "-" => |a, b| a - b,
+            "*" => |a, b| a * b,
+            "/" => |a, b| a / b,
+            "%" => |a, b| a % b,
+            "pow" => f64::powf,
+            "min" => f64::min,
+            "max" => f64::max,
+            "&" => |a, b| (a as i64 & b as i64) as f64,
+            "|" => |a, b| (a as i64 | b as i64) as f64,
+            "^" => |a, b| (a as i64 ^ b as i64) as f64,
+            "<<" => |a, b| ((a as i64) << (b as i64)) as f64,
+            ">>" => |a, b| (a as i64 >> b as i64) as f64,
+            "<" => |a, b| (a < b) as u8 as f64,
+            ">" => |a, b| (a > b) as u8 as f64,
+            "<=" => |a, b| (a <= b) as u8 as f64,
+            ">=" => |a, b| (a >= b) as u8 as f64,
+            "==" => |a, b| (a == b) as u8 as f64,
+            "!=" => |a, b| (a != b) as u8 as f64,
+            _ => return Ok(left),
+        };
+        self.next()?;
+        Ok(operation(left, self.expression()?))
+    }
+
+    fn term(&mut self) -> Result<f64, String>{
+        let token = self.next()?;
+        let unary: fn(f64) -> f64 = match token.as_str(){
+            "(" => {
+                let value = self.expression()?;
+                self.expect(")")?;
+                return Ok(value);
+            },
+            "-" => |a| -a,
+            "~" => |a| !(a as i64) as f64,
+            "!" => |a| (a == 0.0) as u8 as f64,
+            "sin" => f64::sin,
+            "cos" => f64::cos,
+            "tan" => f64::tan,
+            "exp" => f64::exp,
+            "log" => f64::ln,
+            "abs" => f64::abs,
+            "sqrt" => f64::sqrt,
+            "sign" => f64::signum,
+            "ceil" => f64::ceil,
+            "floor" => f64::floor,
+            "@" => {
+                let address = self.term()?;
+                let address = self.fit(address, 0.0, (MEMORY_SIZE - 1) as f64)?;
+                return Ok(self.rom[address as usize] as f64);
+            },
+            "HERE" => return Ok(self.here as f64),
+            "PI" => return Ok(PI),
+            "E" => return Ok(E),
+            _ => return self.value(&token),
+        };
+        Ok(unary(self.term()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+    use crate::machine::Machine;
+    use crate::quirks::Quirks;
+    use crate::rom::Rom;
+    use crate::timing::Timing;
+
+    fn cartridge_gif(json: &str) -> Vec<u8> {
+        let mut payload = (json.len() as u32).to_be_bytes().to_vec();
+        payload.extend_from_slice(json.as_bytes());
+        let mut pixels: Vec<u8> = payload.iter().flat_map(|byte| [byte >> 6, byte >> 4 & 0x3, byte >> 2 & 0x3, byte & 0x3]).collect();
+        pixels.resize(pixels.len().next_multiple_of(64), 0);
+        // The label bits above the payload must not matter.
+        for (index, pixel) in pixels.iter_mut().enumerate() {
+            *pixel |= (index % 3) as u8 * 4;
+        }
+
+        let mut gif = Vec::new();
+        let palette: Vec<u8> = (0..16).flat_map(|index| [index * 16, index * 8, index]).collect();
+        let mut encoder = gif::Encoder::new(&mut gif, 64, (pixels.len() / 64) as u16, &palette).unwrap();
+        let frame = gif::Frame::from_indexed_pixels(64, (pixels.len() / 64) as u16, pixels, None);
+        encoder.write_frame(&frame).unwrap();
+        drop(encoder);
+        gif
+    }
+
+    #[test]
+    fn assembles_statements() {
+        let source = "
+            : main
+                clear
+                v0 := 5 v1 += -1 v2 -= 2 va := vb vf <<= v1
+                i := sprite bcd v3 save v4 load v5
+                sprite v0 v1 15
+                v3 := random 0xFF v4 := key delay := v4
+                loop
+                    if v0 == 3 then v0 += 1
+                    if v0 != v1 then return
+                    if v0 key then jump main
+                while v1 > 0
+                again
+                sub
+                ;
+            : sub return
+            : sprite 0b10000001 0x42
+        ";
+        assert_eq!(assemble(source).unwrap(), vec![
+            0x12, 0x02, 0x00, 0xE0, 0x60, 0x05, 0x71, 0xFF, 0x72, 0xFE, 0x8A, 0xB0, 0x8F, 0x1E,
+            0xA2, 0x3A, 0xF3, 0x33, 0xF4, 0x55, 0xF5, 0x65, 0xD0, 0x1F, 0xC3, 0xFF, 0xF4, 0x0A, 0xF4, 0x15,
+            0x40, 0x03, 0x70, 0x01, 0x50, 0x10, 0x00, 0xEE, 0xE0, 0xA1, 0x12, 0x02,
+            0x6F, 0x00, 0x8F, 0x15, 0x3F, 0x00, 0x12, 0x34, 0x12, 0x1E, 0x22, 0x38, 0x00, 0xEE,
+            0x00, 0xEE, 0x81, 0x42,
+        ]);
+    }
+
+    #[test]
+    fn begin_else_end_branches() {
+        let source = ": main if v0 < v1 begin v2 := 1 else v2 := 2 end";
+        assert_eq!(assemble(source).unwrap(), vec![
+            0x12, 0x02, 0x8F, 0x00, 0x8F, 0x15, 0x3F, 0x00, 0x12, 0x0E, 0x62, 0x01, 0x12, 0x10, 0x62, 0x02,
+        ]);
+    }
+
+    #[test]
+    fn directives() {
+        let source = "
+            :const size 3
+            :alias x v5
+            :calc double { size * 2 + 1 }
+            :macro twice op { op op }
+            : main
+                x := double
+                twice clear
+                :unpack 0xA data
+                i := long data
+                :byte { size - 4 }
+            :org 0x300
+            : data
+        ";
+        assert_eq!(assemble(source).unwrap()[..16], [
+            0x12, 0x02, 0x65, 0x09, 0x00, 0xE0, 0x00, 0xE0, 0x60, 0xA3, 0x61, 0x00, 0xF0, 0x00, 0x03, 0x00,
+        ]);
+        assert_eq!(assemble(source).unwrap()[16], 0xFF);
+    }
+
+    #[test]
+    fn reports_errors_with_line_numbers() {
+        assert_eq!(assemble("clear").err().unwrap(), "line 1: the program has no main label");
+        assert_eq!(assemble(": main\n  jump nowhere").err().unwrap(), "line 2: undefined name 'nowhere'");
+        assert_eq!(assemble(": main\nv0 := 256").err().unwrap(), "line 2: 256 does not fit between -128 and 255");
+        assert!(assemble(": main : main").is_err());
+        assert!(assemble(": main loop").is_err());
+        assert!(assemble(": main :stringmode").is_err());
+    }
+
+    #[test]
+    fn decodes_cartridge_and_options() {
+        let json = r##"{"program": ": main clear", "options": {"tickrate": 20, "maxSize": 3583, "vBlankQuirks": true,
+            "backgroundColor": "#000000", "fillColor": "#FF0000", "fillColor2": "#00FF00", "blendColor": "#0000FF"}}"##;
+        let cartridge = Cartridge::decode(&cartridge_gif(json)).unwrap();
+        assert_eq!(cartridge.program, ": main clear");
+        assert_eq!(cartridge.platform(2), Platform::SuperChip);
+        let info = cartridge.info("game", Platform::SuperChip);
+        assert_eq!(info.tickrate, Some(20));
+        assert_eq!(info.display_wait, Some(true));
+        assert_eq!(info.colors, [[0, 0, 0], [0xFF, 0, 0], [0, 0xFF, 0], [0, 0, 0xFF]]);
+
+        let plain = Cartridge::decode(&cartridge_gif(r#"{"program": ""}"#)).unwrap();
+        assert_eq!(plain.platform(0x1000), Platform::XoChip);
+        assert_eq!(plain.info("game", Platform::Chip8).palette().map(|palette| palette.name().to_string()), None);
+        assert!(Cartridge::decode(&cartridge_gif("not json")).is_err());
+        assert!(Cartridge::decode(b"GIF89a").is_err());
+    }
+
+    #[test]
+    fn cartridges_load_as_roms() {
+        let json = r#"{"program": ": main i := hex v0 sprite v0 v0 5 loop again", "options": {"tickrate": 7}}"#;
+        let path = std::env::temp_dir().join(format!("yachip_8_cartridge_{}.gif", std::process::id()));
+        fs::write(&path, cartridge_gif(json)).unwrap();
+        let rom = Rom::load(&path, |_| None).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(rom.platform, Platform::Chip8);
+        let info = rom.info.unwrap();
+        assert_eq!(info.title, path.file_stem().unwrap().to_str().unwrap());
+        assert_eq!(info.tickrate, Some(7));
+
+        let mut machine = Machine::new(&rom.data, 0, rom.platform, Quirks::default(), Timing::Fixed(7)).unwrap();
+        machine.run_frame(&[0; 16]).unwrap();
+        assert_eq!(machine.display().framebuffer()[..4], [1, 1, 1, 1]);
+        assert!(Rom::load(Path::new("missing.gif"), |_| None).is_err());
+    }
+}
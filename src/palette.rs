@@ -0,0 +1,81 @@
+pub const PALETTES: [(&str, [[u8; 3]; 4]); 5] = [
+    ("classic", [[0x00, 0x00, 0x00], [0xFF, 0xFF, 0xFF], [0xAA, 0xAA, 0xAA], [0x55, 0x55, 0x55]]),
+    ("phosphor", [[0x0A, 0x14, 0x0A], [0x33, 0xFF, 0x66], [0x1A, 0x99, 0x40], [0x99, 0xFF, 0xB3]]),
+    ("amber", [[0x1A, 0x10, 0x00], [0xFF, 0xB0, 0x00], [0x99, 0x66, 0x00], [0xFF, 0xD8, 0x80]]),
+    ("gameboy", [[0x9B, 0xBC, 0x0F], [0x0F, 0x38, 0x0F], [0x30, 0x62, 0x30], [0x8B, 0xAC, 0x0F]]),
+    ("high-contrast", [[0x00, 0x00, 0x00], [0xFF, 0xFF, 0x00], [0x00, 0xFF, 0xFF], [0xFF, 0xFF, 0xFF]]),
+];
+
+#[derive(Clone)]
+pub struct Palette{
+    name: String,
+    colors: [[u8; 3]; 4],
+}
+
+impl Default for Palette{
+    fn default() -> Palette{
+        Palette::new()
+    }
+}
+
+impl Palette{
+    pub fn new() -> Palette{
+        Palette::named(PALETTES[0].0).unwrap()
+    }
+
+    pub fn named(name: &str) -> Option<Palette>{
+        PALETTES.iter().find(|(palette, _)| *palette == name).map(|&(name, colors)| Palette{
+            name: name.to_string(),
+            colors,
+        })
+    }
+
+    pub fn name(&self) -> &str{
+        &self.name
+    }
+
+    pub fn color(&self, pixel: u8) -> [u8; 3]{
+        self.colors[(pixel & 0x3) as usize]
+    }
+
+    pub fn set_color(&mut self, pixel: u8, color: [u8; 3]){
+        self.colors[(pixel & 0x3) as usize] = color;
+        self.name = "custom".to_string();
+    }
+
+    pub fn set_foreground(&mut self, color: [u8; 3]){
+        self.set_color(1, color);
+    }
+
+    pub fn set_background(&mut self, color: [u8; 3]){
+        self.set_color(0, color);
+    }
+
+    pub fn next(&self) -> Palette{
+        let index = PALETTES.iter().position(|(name, _)| *name == self.name).map_or(0, |i| (i + 1) % PALETTES.len());
+        Palette::named(PALETTES[index].0).unwrap()
+    }
+}
+
+pub fn parse_color(value: &str) -> Result<[u8; 3], String>{
+    let hex = value.trim_start_matches('#');
+    if hex.len() != 6 || !hex.chars().all(|digit| digit.is_ascii_hexdigit()){
+        return Err(format!("invalid colour: {}", value));
+    }
+    let rgb = u32::from_str_radix(hex, 16).map_err(|_| format!("invalid colour: {}", value))?;
+    Ok([(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_color_needs_six_hex_digits() {
+        assert_eq!(parse_color("#ff8000"), Ok([0xff, 0x80, 0]));
+        assert_eq!(parse_color("00FF10"), Ok([0, 0xff, 0x10]));
+        assert!(parse_color("+fffff").is_err());
+        assert!(parse_color("#fff").is_err());
+        assert!(parse_color("#gg0000").is_err());
+    }
+}
@@ -0,0 +1,78 @@
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Platform{
+    Chip8,
+    Chip8X,
+    Eti660,
+    SuperChip,
+    XoChip,
+}
+
+pub const PLATFORMS: [(&str, Platform); 5] = [
+    ("chip8", Platform::Chip8),
+    ("chip8x", Platform::Chip8X),
+    ("eti-660", Platform::Eti660),
+    ("schip", Platform::SuperChip),
+    ("xo-chip", Platform::XoChip),
+];
+
+impl Platform{
+    pub fn named(name: &str) -> Option<Platform>{
+        PLATFORMS.iter().find(|(platform, _)| *platform == name).map(|&(_, platform)| platform)
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Platform>{
+        match extension.to_ascii_lowercase().as_str(){
+            "ch8" | "c8" => Some(Platform::Chip8),
+            "c8x" => Some(Platform::Chip8X),
+            "sc8" => Some(Platform::SuperChip),
+            "xo8" => Some(Platform::XoChip),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str{
+        PLATFORMS.iter().find(|(_, platform)| *platform == self).map(|(name, _)| *name).unwrap()
+    }
+
+    pub fn memory_size(self) -> usize{
+        match self{
+            Platform::XoChip => 0x10000,
+            _ => 0x1000,
+        }
+    }
+
+    pub fn start_address(self) -> u16{
+        match self{
+            Platform::Eti660 => 0x600,
+            _ => 0x200,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn platform_from_extension() {
+        assert_eq!(Platform::from_extension("ch8"), Some(Platform::Chip8));
+        assert_eq!(Platform::from_extension("C8X"), Some(Platform::Chip8X));
+        assert_eq!(Platform::from_extension("sc8"), Some(Platform::SuperChip));
+        assert_eq!(Platform::from_extension("xo8"), Some(Platform::XoChip));
+        assert_eq!(Platform::from_extension("txt"), None);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for (name, platform) in PLATFORMS {
+            assert_eq!(Platform::named(name), Some(platform));
+            assert_eq!(platform.name(), name);
+        }
+    }
+
+    #[test]
+    fn eti_660_programs_start_at_0x600() {
+        assert_eq!(Platform::Eti660.start_address(), 0x600);
+        assert_eq!(Platform::Chip8.start_address(), 0x200);
+    }
+}
@@ -0,0 +1,6 @@
+#[derive(Clone, Copy, Default)]
+pub struct Quirks{
+    pub display_wait: bool,
+    pub index_overflow: bool,
+    pub strict_memory: bool,
+}
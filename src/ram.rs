@@ -1,27 +1,50 @@
-use std::fs::File;
-use std::io::Read;
-use std::path::PathBuf;
-
+use crate::state::{StateReader, StateWriter};
 use crate::RAM_SIZE;
 use crate::FONTSET;
 
 pub struct RAM{
-    ram: [u8; RAM_SIZE],
+    ram: Vec<u8>,
+}
+
+impl Default for RAM {
+    fn default() -> RAM {
+        RAM::new()
+    }
 }
 
 impl RAM {
     pub fn new() -> RAM {
+        RAM::with_size(RAM_SIZE)
+    }
+
+    pub fn with_size(size: usize) -> RAM {
+        assert!(size.is_power_of_two() && size <= 0x10000);
         RAM {
-            ram: [0; RAM_SIZE],
+            ram: vec![0; size],
         }
     }
 
+    pub fn size(&self) -> usize {
+        self.ram.len()
+    }
+
     pub fn read(&self, address: u16) -> u8 {
-        self.ram[address as usize]
+        self.ram[address as usize & (self.ram.len() - 1)]
     }
 
     pub fn write(&mut self, address: u16, data: u8) {
-        self.ram[address as usize] = data;
+        let mask = self.ram.len() - 1;
+        self.ram[address as usize & mask] = data;
+    }
+
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.bytes(&self.ram);
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), String> {
+        let size = self.ram.len();
+        self.ram.copy_from_slice(reader.bytes(size)?);
+        Ok(())
     }
 
     pub fn load_fontset(&mut self) {
@@ -30,13 +53,26 @@ impl RAM {
         }
     }
 
-    pub fn load_rom(&mut self, rom: &PathBuf) {
-        let mut file = File::open(rom).unwrap();
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer).unwrap();
-
-        for (i, &byte) in buffer.iter().enumerate() {
-            self.ram[0x200 + i] = byte;
+    pub fn load_rom(&mut self, rom: &[u8], address: u16) -> Result<(), String> {
+        let start = address as usize;
+        let available = self.ram.len().saturating_sub(start);
+        if rom.len() > available {
+            return Err(format!("rom is {} bytes but only {} fit in memory from {:#05x}", rom.len(), available, address));
         }
+        self.ram[start..start + rom.len()].copy_from_slice(rom);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_rom_rejects_rom_larger_than_memory() {
+        let mut ram = RAM::new();
+        assert!(ram.load_rom(&[0xFF; 0xE00], 0x200).is_ok());
+        assert_eq!(ram.read(0xFFF), 0xFF);
+        assert_eq!(ram.load_rom(&[0; 0xE01], 0x200), Err("rom is 3585 bytes but only 3584 fit in memory from 0x200".to_string()));
     }
 }
@@ -0,0 +1,91 @@
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::render::{Canvas, Texture, TextureCreator};
+use sdl2::video::{FullscreenType, Window, WindowContext};
+use sdl2::rect::Rect;
+
+use yachip_8::display::Display;
+use yachip_8::filter::Filter;
+use yachip_8::frontend::Renderer;
+use yachip_8::palette::Palette;
+use yachip_8::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+use crate::config::Scaling;
+
+pub struct SdlRenderer {
+    canvas: Canvas<Window>,
+    texture_creator: TextureCreator<WindowContext>,
+    texture: Option<Texture>,
+    filter: Filter,
+    scaling: Scaling,
+}
+
+impl SdlRenderer {
+    pub fn new(sdl2_context: &sdl2::Sdl, title: &str, filter: Filter, scale: u32, scaling: Scaling) -> SdlRenderer {
+        let video_subsystem = sdl2_context.video().unwrap();
+        let window = video_subsystem
+            .window(title, SCREEN_WIDTH as u32 * scale, SCREEN_HEIGHT as u32 * scale)
+            .position_centered()
+            .resizable()
+            .build()
+            .unwrap();
+        let canvas = window.into_canvas().build().unwrap();
+        SdlRenderer {
+            texture_creator: canvas.texture_creator(),
+            texture: None,
+            canvas,
+            filter,
+            scaling,
+        }
+    }
+}
+
+impl Renderer for SdlRenderer {
+    fn render(&mut self, display: &Display, palette: &Palette) {
+        let (width, height) = (self.filter.width(), self.filter.height());
+        let size = self.texture.as_ref().map(|texture| (texture.query().width, texture.query().height));
+        if size != Some((width as u32, height as u32)) {
+            if let Some(texture) = self.texture.take() {
+                unsafe { texture.destroy() };
+            }
+            self.texture = Some(self.texture_creator
+                .create_texture_streaming(PixelFormatEnum::RGB24, width as u32, height as u32)
+                .unwrap());
+        }
+        let texture = self.texture.as_mut().unwrap();
+        let image = self.filter.apply(display.framebuffer(), palette);
+        texture.update(None, image, width * 3).unwrap();
+
+        self.canvas.set_draw_color(Color::RGB(0, 0, 0));
+        self.canvas.clear();
+        let viewport = viewport(self.canvas.output_size().unwrap(), self.scaling);
+        self.canvas.copy(texture, None, viewport).unwrap();
+        self.canvas.present();
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.canvas.window_mut().set_title(title).unwrap();
+    }
+
+    fn toggle_fullscreen(&mut self) {
+        let window = self.canvas.window_mut();
+        let fullscreen = match window.fullscreen_state() {
+            FullscreenType::Off => FullscreenType::Desktop,
+            _ => FullscreenType::Off,
+        };
+        window.set_fullscreen(fullscreen).unwrap();
+    }
+}
+
+fn viewport((output_width, output_height): (u32, u32), scaling: Scaling) -> Rect {
+    let scale_x = output_width as f32 / SCREEN_WIDTH as f32;
+    let scale_y = output_height as f32 / SCREEN_HEIGHT as f32;
+    let scale = match scaling {
+        Scaling::Integer => scale_x.min(scale_y).floor().max(1.0),
+        Scaling::Fractional => scale_x.min(scale_y),
+    };
+    let width = (SCREEN_WIDTH as f32 * scale) as u32;
+    let height = (SCREEN_HEIGHT as f32 * scale) as u32;
+    let x = (output_width as i32 - width as i32) / 2;
+    let y = (output_height as i32 - height as i32) / 2;
+    Rect::new(x, y, width.max(1), height.max(1))
+}
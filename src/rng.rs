@@ -0,0 +1,34 @@
+use crate::state::{StateReader, StateWriter};
+
+pub struct Rng{
+    state: u64,
+}
+
+impl Rng{
+    pub fn new(seed: u64) -> Rng{
+        Rng{
+            state: seed,
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64{
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub fn save_state(&self, writer: &mut StateWriter){
+        writer.u64(self.state);
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), String>{
+        self.state = reader.u64()?;
+        Ok(())
+    }
+
+    pub fn next_u8(&mut self) -> u8{
+        (self.next_u64() >> 56) as u8
+    }
+}
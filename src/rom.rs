@@ -0,0 +1,156 @@
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+
+use zip::ZipArchive;
+
+use crate::database::RomInfo;
+use crate::octo::{self, Cartridge};
+use crate::platform::Platform;
+
+pub struct Rom{
+    pub data: Vec<u8>,
+    pub platform: Platform,
+    pub info: Option<RomInfo>,
+}
+
+impl Rom{
+    pub fn load<F: FnOnce(&[String]) -> Option<usize>>(path: &Path, choose: F) -> Result<Rom, String>{
+        if path == Path::new("-"){
+            let mut data = Vec::new();
+            io::stdin().read_to_end(&mut data).map_err(|e| format!("stdin: {}", e))?;
+            return Ok(Rom{
+                data,
+                platform: Platform::Chip8,
+                info: None,
+            });
+        }
+
+        let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or("");
+        if extension.eq_ignore_ascii_case("gif"){
+            return Rom::load_cartridge(path).map_err(|e| format!("{}: {}", path.display(), e));
+        }
+        if is_archive(path){
+            return Rom::load_zip(path, choose).map_err(|e| format!("{}: {}", path.display(), e));
+        }
+        let data = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(Rom{
+            data,
+            platform: Platform::from_extension(extension).unwrap_or(Platform::Chip8),
+            info: None,
+        })
+    }
+
+    pub fn entries(path: &Path) -> Result<Vec<String>, String>{
+        let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let archive = ZipArchive::new(file).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(rom_names(&archive))
+    }
+
+    fn load_zip<F: FnOnce(&[String]) -> Option<usize>>(path: &Path, choose: F) -> Result<Rom, String>{
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        let mut archive = ZipArchive::new(file).map_err(|e| e.to_string())?;
+
+        let names = rom_names(&archive);
+        let name = match names.len(){
+            0 => return Err("archive is empty".to_string()),
+            1 => &names[0],
+            _ => names.get(choose(&names).ok_or("no rom selected from archive")?).ok_or("no such rom in archive")?,
+        };
+        let platform = Platform::from_extension(extension(name)).unwrap_or(Platform::Chip8);
+        let mut data = Vec::new();
+        archive.by_name(name).map_err(|e| e.to_string())?
+            .take(platform.memory_size() as u64 + 1)
+            .read_to_end(&mut data).map_err(|e| e.to_string())?;
+        Ok(Rom{
+            data,
+            platform,
+            info: None,
+        })
+    }
+
+    fn load_cartridge(path: &Path) -> Result<Rom, String>{
+        let cartridge = Cartridge::decode(&fs::read(path).map_err(|e| e.to_string())?)?;
+        let data = octo::assemble(&cartridge.program)?;
+        let platform = cartridge.platform(data.len());
+        let title = path.file_stem().map_or(String::new(), |stem| stem.to_string_lossy().into_owned());
+        Ok(Rom{
+            info: Some(cartridge.info(&title, platform)),
+            data,
+            platform,
+        })
+    }
+}
+
+pub fn is_archive(path: &Path) -> bool{
+    path.extension().and_then(|extension| extension.to_str()).is_some_and(|extension| extension.eq_ignore_ascii_case("zip"))
+}
+
+fn rom_names(archive: &ZipArchive<File>) -> Vec<String>{
+    let files: Vec<String> = archive.file_names()
+        .filter(|name| !name.ends_with('/'))
+        .map(|name| name.to_string())
+        .collect();
+    let mut names: Vec<String> = files.iter()
+        .filter(|name| Platform::from_extension(extension(name)).is_some())
+        .cloned()
+        .collect();
+    if names.is_empty(){
+        names = files;
+    }
+    names.sort();
+    names
+}
+
+fn extension(name: &str) -> &str{
+    Path::new(name).extension().and_then(|extension| extension.to_str()).unwrap_or("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::{FileOptions, ZipWriter};
+
+    fn write_zip(name: &str, files: &[(&str, &[u8])]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("yachip_8_{}_{}", std::process::id(), name));
+        let mut writer = ZipWriter::new(File::create(&path).unwrap());
+        for (file, data) in files {
+            writer.start_file(*file, FileOptions::default()).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn zip_with_one_rom_loads_it() {
+        let path = write_zip("single.zip", &[("readme.txt", b"hello"), ("game.sc8", &[0x00, 0xE0])]);
+        let rom = Rom::load(&path, |_| panic!("should not ask")).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(rom.data, vec![0x00, 0xE0]);
+        assert_eq!(rom.platform, Platform::SuperChip);
+    }
+
+    #[test]
+    fn zip_with_several_roms_asks() {
+        let path = write_zip("several.zip", &[("b.ch8", &[0x02]), ("a.ch8", &[0x01])]);
+        let rom = Rom::load(&path, |names| {
+            assert_eq!(names, ["a.ch8", "b.ch8"]);
+            Some(1)
+        }).unwrap();
+        assert_eq!(rom.data, vec![0x02]);
+        assert!(Rom::load(&path, |_| None).is_err());
+        assert_eq!(Rom::entries(&path).unwrap(), ["a.ch8", "b.ch8"]);
+        assert!(is_archive(&path));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn zip_entries_are_read_up_to_memory_size() {
+        let path = write_zip("large.zip", &[("large.ch8", &vec![0; 0x100000])]);
+        let rom = Rom::load(&path, |_| None).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(rom.data.len(), Platform::Chip8.memory_size() + 1);
+    }
+}
@@ -1,81 +1,54 @@
 use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
 
-use crate::timer::Timer;
+use yachip_8::audio::{AudioSink, NullSink, SquareWave, SAMPLE_RATE};
 
-const SAMPLE_RATE: i32 = 44100;
-const AMPLITUDE: f32 = 0.25;
+struct Callback(SquareWave);
 
-struct SquareWave {
-    phase_inc: f32,
-    phase: f32,
-    volume: f32,
-}
-
-impl Iterator for SquareWave {
-    type Item = f32;
-
-    fn next(&mut self) -> Option<f32> {
-        self.phase = (self.phase + self.phase_inc) % (2.0 * std::f32::consts::PI);
-
-        let value = if self.phase < std::f32::consts::PI {
-            self.volume
-        } else {
-            -self.volume
-        };
+impl AudioCallback for Callback {
+    type Channel = f32;
 
-        Some(value)
+    fn callback(&mut self, out: &mut [f32]) {
+        for sample in out.iter_mut() {
+            *sample = self.0.next().unwrap();
+        }
     }
 }
 
-pub struct Sound {
-    device: Option<AudioDevice<SquareWave>>,
-    frequency: i32,
-    duration: u8,
+pub struct SdlSink {
+    device: AudioDevice<Callback>,
 }
 
-impl Sound {
-    pub fn new(sdl_context: &sdl2::Sdl) -> Self {
-        let audio_subsystem = sdl_context.audio().unwrap();
+impl SdlSink {
+    pub fn new(sdl_context: &sdl2::Sdl) -> Result<SdlSink, String> {
+        let audio_subsystem = sdl_context.audio()?;
         let desired_spec = AudioSpecDesired {
-            freq: Some(SAMPLE_RATE as i32),
+            freq: Some(SAMPLE_RATE),
             channels: Some(1),
             samples: None,
         };
-
-        Sound {
-            device: Some(
-                audio_subsystem
-                    .open_playback(None, &desired_spec, |spec| {
-                        SquareWave {
-                            phase_inc: 2.0 * std::f32::consts::PI * 440.0 / spec.freq as f32,
-                            phase: 0.0,
-                            volume: AMPLITUDE,
-                        }
-                    })
-                    .unwrap(),
-            ),
-            frequency: 440,
-            duration: 0,
-        }
+        let device = audio_subsystem.open_playback(None, &desired_spec, |spec| Callback(SquareWave::new(spec.freq)))?;
+        Ok(SdlSink {
+            device,
+        })
     }
+}
 
-    pub fn play_sound(&mut self, timer: &mut Timer) {
-        if self.duration > 0 {
-            self.frequency = 440;
-            self.duration = timer.read_sound_timer();
-            self.device.as_mut().unwrap().resume();
-        } else if let Some(device) = &self.device {
-            device.pause();
+impl AudioSink for SdlSink {
+    fn play(&mut self, tone: bool) {
+        if tone {
+            self.device.resume();
+        } else {
+            self.device.pause();
         }
     }
 }
 
-impl AudioCallback for SquareWave {
-    type Channel = f32;
-
-    fn callback(&mut self, out: &mut [f32]) {
-        for sample in out.iter_mut() {
-            *sample = self.next().unwrap();
-        }
+pub fn open(sdl_context: &sdl2::Sdl) -> Box<dyn AudioSink> {
+    match SdlSink::new(sdl_context) {
+        Ok(sink) => Box::new(sink),
+        Err(error) => {
+            eprintln!("warning: can't open an audio device ({}), running without sound", error);
+            Box::new(NullSink)
+        },
     }
 }
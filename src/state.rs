@@ -0,0 +1,99 @@
+const STATE_HEADER: &[u8; 8] = b"YACHIP8S";
+const STATE_VERSION: u8 = 2;
+
+pub struct StateWriter{
+    data: Vec<u8>,
+}
+
+impl Default for StateWriter{
+    fn default() -> StateWriter{
+        StateWriter::new()
+    }
+}
+
+impl StateWriter{
+    pub fn new() -> StateWriter{
+        let mut data = STATE_HEADER.to_vec();
+        data.push(STATE_VERSION);
+        StateWriter{
+            data,
+        }
+    }
+
+    pub fn u8(&mut self, value: u8){
+        self.data.push(value);
+    }
+
+    pub fn u16(&mut self, value: u16){
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64){
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]){
+        self.u64(bytes.len() as u64);
+        self.data.extend_from_slice(bytes);
+    }
+
+    pub fn finish(self) -> Vec<u8>{
+        self.data
+    }
+}
+
+pub struct StateReader<'a>{
+    data: &'a [u8],
+}
+
+impl<'a> StateReader<'a>{
+    pub fn new(data: &'a [u8]) -> Result<StateReader<'a>, String>{
+        let mut reader = StateReader{
+            data,
+        };
+        if reader.take(STATE_HEADER.len())? != STATE_HEADER{
+            return Err("not a save state".to_string());
+        }
+        let version = reader.u8()?;
+        if version != STATE_VERSION{
+            return Err(format!("unsupported save state version {}", version));
+        }
+        Ok(reader)
+    }
+
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String>{
+        if self.data.len() < count{
+            return Err("save state is truncated".to_string());
+        }
+        let (taken, rest) = self.data.split_at(count);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, String>{
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, String>{
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, String>{
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub fn bytes(&mut self, expected: usize) -> Result<&'a [u8], String>{
+        let length = self.u64()? as usize;
+        if length != expected{
+            return Err(format!("save state holds {} bytes where {} were expected", length, expected));
+        }
+        self.take(length)
+    }
+
+    pub fn finish(self) -> Result<(), String>{
+        match self.data.len(){
+            0 => Ok(()),
+            extra => Err(format!("save state has {} unexpected trailing bytes", extra)),
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use std::io::{stdout, Stdout, Write};
+use std::time::Duration;
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen, SetTitle};
+use crossterm::{execute, queue};
+
+use yachip_8::display::Display;
+use yachip_8::filter::Filter;
+use yachip_8::frontend::{AudioSink, Command, InputSource, Renderer};
+use yachip_8::graphics::{self, Protocol};
+use yachip_8::palette::Palette;
+use yachip_8::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+const HOLD_FRAMES: u8 = 8;
+const BRAILLE: u32 = 0x2800;
+const BRAILLE_DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+#[derive(Clone, Copy)]
+pub enum Output {
+    HalfBlocks,
+    Braille,
+    Graphics(Protocol),
+}
+
+pub struct TerminalRenderer {
+    stdout: Stdout,
+    filter: Filter,
+    output: Output,
+    scale: u32,
+    last_frame: Vec<u8>,
+    enhanced_keyboard: bool,
+}
+
+impl TerminalRenderer {
+    pub fn new(filter: Filter, output: Output, scale: u32) -> TerminalRenderer {
+        let mut stdout = stdout();
+        terminal::enable_raw_mode().unwrap();
+        execute!(stdout, EnterAlternateScreen, Hide, Clear(ClearType::All)).unwrap();
+        let enhanced_keyboard = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if enhanced_keyboard {
+            execute!(stdout, PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)).unwrap();
+        }
+        TerminalRenderer {
+            stdout,
+            filter,
+            output,
+            scale,
+            last_frame: Vec::new(),
+            enhanced_keyboard,
+        }
+    }
+
+    pub fn release_events(&self) -> bool {
+        self.enhanced_keyboard
+    }
+
+    fn half_blocks(&mut self, display: &Display, palette: &Palette) -> Vec<u8> {
+        let (width, height) = (self.filter.width(), self.filter.height());
+        let image = self.filter.apply(display.framebuffer(), palette);
+        let pixel = |x: usize, y: usize| {
+            let index = (y * width + x) * 3;
+            Color::Rgb { r: image[index], g: image[index + 1], b: image[index + 2] }
+        };
+
+        let mut out = Vec::new();
+        for row in 0..height / 2 {
+            queue!(out, MoveTo(0, row as u16)).unwrap();
+            let mut colors = None;
+            for x in 0..width {
+                let (top, bottom) = (pixel(x, row * 2), pixel(x, row * 2 + 1));
+                if colors != Some((top, bottom)) {
+                    queue!(out, SetForegroundColor(top), SetBackgroundColor(bottom)).unwrap();
+                    colors = Some((top, bottom));
+                }
+                queue!(out, Print('▀')).unwrap();
+            }
+            queue!(out, ResetColor).unwrap();
+        }
+        out
+    }
+
+    fn graphics(&mut self, protocol: Protocol, display: &Display, palette: &Palette) -> Vec<u8> {
+        let (width, height) = (self.filter.width(), self.filter.height());
+        let image = self.filter.apply(display.framebuffer(), palette);
+        let scale = self.scale as usize * SCREEN_HEIGHT / height;
+        let mut out = Vec::new();
+        queue!(out, MoveTo(0, 0), Print(graphics::encode(protocol, image, width, height, scale.max(1)))).unwrap();
+        out
+    }
+
+    fn braille(&self, display: &Display, palette: &Palette) -> Vec<u8> {
+        let framebuffer = display.framebuffer();
+        let [r, g, b] = palette.color(1);
+        let foreground = Color::Rgb { r, g, b };
+        let [r, g, b] = palette.color(0);
+        let background = Color::Rgb { r, g, b };
+
+        let mut out = Vec::new();
+        for row in 0..SCREEN_HEIGHT / 4 {
+            queue!(out, MoveTo(0, row as u16), SetForegroundColor(foreground), SetBackgroundColor(background)).unwrap();
+            for column in 0..SCREEN_WIDTH / 2 {
+                let mut dots = 0;
+                for (dy, line) in BRAILLE_DOTS.iter().enumerate() {
+                    for (dx, dot) in line.iter().enumerate() {
+                        if framebuffer[(row * 4 + dy) * SCREEN_WIDTH + column * 2 + dx] != 0 {
+                            dots |= dot;
+                        }
+                    }
+                }
+                queue!(out, Print(char::from_u32(BRAILLE + dots as u32).unwrap())).unwrap();
+            }
+            queue!(out, ResetColor).unwrap();
+        }
+        out
+    }
+}
+
+impl Renderer for TerminalRenderer {
+    fn render(&mut self, display: &Display, palette: &Palette) {
+        let frame = match self.output {
+            Output::HalfBlocks => self.half_blocks(display, palette),
+            Output::Braille => self.braille(display, palette),
+            Output::Graphics(protocol) => self.graphics(protocol, display, palette),
+        };
+        if frame != self.last_frame {
+            self.stdout.write_all(&frame).unwrap();
+            self.stdout.flush().unwrap();
+            self.last_frame = frame;
+        }
+    }
+
+    fn set_title(&mut self, title: &str) {
+        execute!(self.stdout, SetTitle(title), Clear(ClearType::All)).unwrap();
+        self.last_frame.clear();
+    }
+}
+
+impl Drop for TerminalRenderer {
+    fn drop(&mut self) {
+        if self.enhanced_keyboard {
+            let _ = execute!(self.stdout, PopKeyboardEnhancementFlags);
+        }
+        let _ = execute!(self.stdout, ResetColor, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+pub struct TerminalInput {
+    keys: [u8; 16],
+    hold: [u8; 16],
+    bindings: Vec<(KeyCode, usize)>,
+    release_events: bool,
+}
+
+impl TerminalInput {
+    pub fn new(release_events: bool) -> TerminalInput {
+        TerminalInput {
+            keys: [0; 16],
+            hold: [0; 16],
+            bindings: Vec::new(),
+            release_events,
+        }
+    }
+
+    fn key(&self, code: KeyCode) -> Option<usize> {
+        if let Some(&(_, key)) = self.bindings.iter().find(|(bound, _)| *bound == code) {
+            return Some(key);
+        }
+        match code {
+            KeyCode::Char(character) => character.to_digit(16).map(|key| key as usize),
+            _ => None,
+        }
+    }
+
+    fn press(&mut self, event: KeyEvent, commands: &mut Vec<Command>) {
+        if let Some(key) = self.key(event.code) {
+            self.hold[key] = match event.kind {
+                KeyEventKind::Release => 0,
+                _ if self.release_events => u8::MAX,
+                _ => HOLD_FRAMES,
+            };
+        }
+        if event.kind == KeyEventKind::Release {
+            return;
+        }
+        match event.code {
+            KeyCode::Char('c') if event.modifiers.contains(KeyModifiers::CONTROL) => commands.push(Command::Quit),
+            KeyCode::Char('q') => commands.push(Command::Quit),
+            KeyCode::Char('p') => commands.push(Command::NextPalette),
+            KeyCode::Up => commands.push(Command::Up),
+            KeyCode::Down => commands.push(Command::Down),
+            KeyCode::Enter => commands.push(Command::Select),
+            KeyCode::Esc => commands.push(Command::Back),
+            _ => {}
+        }
+    }
+}
+
+impl InputSource for TerminalInput {
+    fn poll(&mut self) -> Vec<Command> {
+        for hold in self.hold.iter_mut() {
+            if *hold != u8::MAX {
+                *hold = hold.saturating_sub(1);
+            }
+        }
+
+        let mut commands = Vec::new();
+        while event::poll(Duration::ZERO).unwrap_or(false) {
+            if let Ok(Event::Key(event)) = event::read() {
+                self.press(event, &mut commands);
+            }
+        }
+
+        for (key, hold) in self.keys.iter_mut().zip(self.hold) {
+            *key = (hold > 0) as u8;
+        }
+        commands
+    }
+
+    fn keys(&self) -> [u8; 16] {
+        self.keys
+    }
+
+    fn bind(&mut self, name: &str, key: u8) {
+        let code = match name {
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "a" => KeyCode::Char('z'),
+            "b" => KeyCode::Char('x'),
+            _ => return,
+        };
+        self.bindings.push((code, (key & 0xF) as usize));
+    }
+
+    fn reset_bindings(&mut self) {
+        self.bindings.clear();
+        self.keys = [0; 16];
+        self.hold = [0; 16];
+    }
+}
+
+pub struct Bell {
+    playing: bool,
+}
+
+impl Bell {
+    pub fn new() -> Bell {
+        Bell {
+            playing: false,
+        }
+    }
+}
+
+impl AudioSink for Bell {
+    fn play(&mut self, tone: bool) {
+        if tone && !self.playing {
+            let mut stdout = stdout();
+            let _ = stdout.write_all(b"\x07");
+            let _ = stdout.flush();
+        }
+        self.playing = tone;
+    }
+}
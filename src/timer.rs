@@ -1,8 +1,16 @@
+use crate::state::{StateReader, StateWriter};
+
 pub struct Timer{
     delay_timer: u8,
     sound_timer: u8,
 }
 
+impl Default for Timer {
+    fn default() -> Timer {
+        Timer::new()
+    }
+}
+
 impl Timer {
     pub fn new() -> Timer {
         Timer {
@@ -27,4 +35,19 @@ impl Timer {
         self.sound_timer = value;
     }
 
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.u8(self.delay_timer);
+        writer.u8(self.sound_timer);
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), String> {
+        self.delay_timer = reader.u8()?;
+        self.sound_timer = reader.u8()?;
+        Ok(())
+    }
+
+    pub fn tick(&mut self) {
+        self.delay_timer = self.delay_timer.saturating_sub(1);
+        self.sound_timer = self.sound_timer.saturating_sub(1);
+    }
 }
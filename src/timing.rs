@@ -0,0 +1,83 @@
+// Machine cycles (8 clocks each at 1.76 MHz) the COSMAC VIP interpreter spends
+// fetching and decoding every instruction.
+const FETCH_CYCLES: u32 = 40;
+
+pub const VIP_FRAME_CYCLES: u32 = 3668;
+pub const VIP_DISPLAY_DMA_CYCLES: u32 = 1024;
+pub const VIP_CYCLES_PER_FRAME: u32 = VIP_FRAME_CYCLES - VIP_DISPLAY_DMA_CYCLES;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Timing{
+    Fixed(u32),
+    Vip,
+}
+
+// Fetch plus the execute cost of each instruction on the COSMAC VIP interpreter.
+pub fn vip_cycles(opcode: u16, x: u8, skipped: bool) -> u32{
+    let skip = |not_taken: u32, taken: u32| if skipped { taken } else { not_taken };
+    let execute = match opcode & 0xF000{
+        0x0000 => match opcode & 0x00FF{
+            0x00E0 => 24,
+            0x00EE => 10,
+            _ => 0,
+        },
+        0x1000 => 12,
+        0x2000 => 26,
+        0x3000 | 0x4000 => skip(10, 12),
+        0x5000 | 0x9000 => skip(14, 16),
+        0x6000 => 6,
+        0x7000 => 10,
+        0x8000 => 44,
+        0xA000 => 12,
+        0xB000 => 22,
+        0xC000 => 36,
+        0xD000 => draw_cycles((opcode & 0x000F) as u32, x),
+        0xE000 => skip(14, 16),
+        0xF000 => match opcode & 0x00FF{
+            0x0007 | 0x000A | 0x0015 | 0x0018 => 10,
+            0x001E => 18,
+            0x0029 => 20,
+            0x0033 => 204,
+            0x0055 | 0x0065 => 133 + 14 * ((opcode & 0x0F00) >> 8) as u32,
+            _ => 0,
+        },
+        _ => 0,
+    };
+    FETCH_CYCLES + execute
+}
+
+fn draw_cycles(height: u32, x: u8) -> u32{
+    let row = if x & 0x7 == 0 { 46 } else { 68 };
+    26 + height * row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draw_cost_grows_with_height_and_misalignment() {
+        assert_eq!(vip_cycles(0xD015, 0, false), 40 + 26 + 5 * 46);
+        assert_eq!(vip_cycles(0xD015, 3, false), 40 + 26 + 5 * 68);
+        assert_eq!(vip_cycles(0xD01F, 8, false), 40 + 26 + 15 * 46);
+        assert_eq!(vip_cycles(0xD010, 1, false), 40 + 26);
+    }
+
+    #[test]
+    fn taken_skips_cost_more() {
+        assert_eq!(vip_cycles(0x3000, 0, false), 50);
+        assert_eq!(vip_cycles(0x3000, 0, true), 52);
+        assert_eq!(vip_cycles(0x4000, 0, true), 52);
+        assert_eq!(vip_cycles(0x5010, 0, false), 54);
+        assert_eq!(vip_cycles(0x9010, 0, true), 56);
+        assert_eq!(vip_cycles(0xE09E, 0, false), 54);
+        assert_eq!(vip_cycles(0xE0A1, 0, true), 56);
+    }
+
+    #[test]
+    fn register_dumps_scale_with_count() {
+        assert_eq!(vip_cycles(0xF055, 0, false), 40 + 133);
+        assert_eq!(vip_cycles(0xF765, 7, false), 40 + 133 + 7 * 14);
+        assert_eq!(vip_cycles(0xFF55, 15, false), 40 + 133 + 15 * 14);
+    }
+}
@@ -0,0 +1,24 @@
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
+pub fn catch<R, F: FnOnce() -> Result<R, String>>(f: F) -> Result<R, String>{
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|panic| Err(panic_message(&*panic)))
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String{
+    panic.downcast_ref::<&str>().map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "panicked".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panics_become_errors() {
+        assert_eq!(catch(|| -> Result<(), String> { panic!("boom") }), Err("boom".to_string()));
+        assert_eq!(catch(|| -> Result<(), String> { panic!("{} at {}", "boom", 3) }), Err("boom at 3".to_string()));
+        assert_eq!(catch(|| Ok(1)), Ok(1));
+    }
+}
@@ -0,0 +1,108 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use yachip_8::machine::Machine;
+use yachip_8::platform::Platform;
+use yachip_8::quirks::Quirks;
+use yachip_8::timing::Timing;
+use yachip_8::{CYCLES_PER_FRAME, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+struct RomCase {
+    rom: &'static str,
+    golden: &'static str,
+    frames: u64,
+    presses: &'static [(u64, u8)],
+}
+
+const PRESS_FRAMES: u64 = 4;
+
+fn rom_path(rom: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("roms").join(rom)
+}
+
+fn golden_path(golden: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden").join(golden)
+}
+
+fn to_pbm(framebuffer: &[u8]) -> String {
+    let mut pbm = format!("P1\n{} {}\n", SCREEN_WIDTH, SCREEN_HEIGHT);
+    for row in framebuffer.chunks(SCREEN_WIDTH) {
+        let line: Vec<&str> = row.iter().map(|&pixel| if pixel != 0 { "1" } else { "0" }).collect();
+        pbm.push_str(&line.join(" "));
+        pbm.push('\n');
+    }
+    pbm
+}
+
+fn run(case: &RomCase) -> String {
+    let path = rom_path(case.rom);
+    let rom = fs::read(&path).unwrap_or_else(|error| panic!("{}: {}", path.display(), error));
+    let mut machine = Machine::new(&rom, 0, Platform::Chip8, Quirks::default(), Timing::Fixed(CYCLES_PER_FRAME)).unwrap();
+    for frame in 0..case.frames {
+        let mut keys = [0; 16];
+        for &(start, key) in case.presses {
+            if frame >= start && frame < start + PRESS_FRAMES {
+                keys[key as usize] = 1;
+            }
+        }
+        machine.run_frame(&keys).unwrap();
+    }
+    to_pbm(machine.display().framebuffer())
+}
+
+fn check(case: &RomCase) {
+    let actual = run(case);
+    let golden = golden_path(case.golden);
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&golden, &actual).unwrap();
+        panic!("wrote {}, check it and rerun without UPDATE_GOLDEN", golden.display());
+    }
+    let expected = fs::read_to_string(&golden)
+        .unwrap_or_else(|_| panic!("missing golden {}, run with UPDATE_GOLDEN=1 to create it", golden.display()));
+    if actual != expected {
+        let output = Path::new(env!("CARGO_TARGET_TMPDIR")).join(case.golden);
+        fs::write(&output, &actual).unwrap();
+        panic!("{} after {} frames does not match {}, actual framebuffer written to {}",
+            case.rom, case.frames, golden.display(), output.display());
+    }
+}
+
+#[test]
+fn ibm_logo() {
+    check(&RomCase { rom: "IBM Logo.ch8", golden: "ibm_logo.pbm", frames: 60, presses: &[] });
+}
+
+#[test]
+fn sqrt_test() {
+    check(&RomCase { rom: "SQRT Test [Sergey Naydenov, 2010].ch8", golden: "sqrt_test.pbm", frames: 300, presses: &[] });
+}
+
+#[test]
+fn flags_test() {
+    check(&RomCase { rom: "Flags Test [yachip-8].ch8", golden: "flags_test.pbm", frames: 60, presses: &[] });
+}
+
+#[test]
+#[ignore = "needs Timendus' chip8-test-suite roms in roms/chip8-test-suite and their goldens"]
+fn chip8_test_suite_corax() {
+    check(&RomCase { rom: "chip8-test-suite/3-corax+.ch8", golden: "corax.pbm", frames: 60, presses: &[] });
+}
+
+#[test]
+#[ignore = "needs Timendus' chip8-test-suite roms in roms/chip8-test-suite and their goldens"]
+fn chip8_test_suite_flags() {
+    check(&RomCase { rom: "chip8-test-suite/4-flags.ch8", golden: "flags.pbm", frames: 120, presses: &[] });
+}
+
+#[test]
+#[ignore = "needs Timendus' chip8-test-suite roms in roms/chip8-test-suite and their goldens"]
+fn chip8_test_suite_quirks() {
+    check(&RomCase { rom: "chip8-test-suite/5-quirks.ch8", golden: "quirks.pbm", frames: 600, presses: &[(10, 0x1)] });
+}
+
+#[test]
+#[ignore = "needs Timendus' chip8-test-suite roms in roms/chip8-test-suite and their goldens"]
+fn chip8_test_suite_keypad() {
+    check(&RomCase { rom: "chip8-test-suite/6-keypad.ch8", golden: "keypad.pbm", frames: 120, presses: &[(10, 0x1), (60, 0x5)] });
+}